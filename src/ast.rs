@@ -1,6 +1,9 @@
 pub mod helpers;
 pub mod pretty_print;
+#[cfg(test)]
+mod tests;
 
+use crate::lexer::literal::Number;
 use crate::node::*;
 
 use std::rc::Rc;
@@ -10,6 +13,12 @@ pub struct Struct<'a, M: NodeMeta> {
     pub fields: Vec<Node<Field<'a, M>, M>>,
 }
 
+/// A type whose values are each exactly one of its variants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum<'a, M: NodeMeta> {
+    pub variants: Vec<Node<Field<'a, M>, M>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Args<'a, M: NodeMeta> {
     pub args: Vec<Node<Arg<'a, M>, M>>,
@@ -17,6 +26,7 @@ pub struct Args<'a, M: NodeMeta> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Arg<'a, M: NodeMeta> {
+    pub name: Option<Ident>,
     pub expr: Node<Expr<'a, M>, M>,
     pub is_mut: bool,
 }
@@ -29,17 +39,55 @@ pub struct Params<'a, M: NodeMeta> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param<'a, M: NodeMeta> {
     pub ident: Ident,
-    pub expr: Node<Expr<'a, M>, M>,
+    /// The type of the parameter, which is inferred if omitted
+    pub expr: Option<Node<Expr<'a, M>, M>>,
     pub is_mut: bool,
+    pub default: Option<Node<Expr<'a, M>, M>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Field<'a, M: NodeMeta> {
     Field(Ident, Node<Expr<'a, M>, M>),
     Inline(Expr<'a, M>),
+    PartialInline(PartialInline<'a, M>),
+    Method(Method<'a, M>),
+    /// The text of the `---` doc comments documenting the field after it
+    Doc(Rc<str>),
     Spacer,
 }
 
+/// Inlines only the listed fields of a struct, as in `..std.(to_string)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialInline<'a, M: NodeMeta> {
+    pub expr: Node<Expr<'a, M>, M>,
+    pub names: Vec<Node<InlineName, M>>,
+}
+
+/// A field selected by partial inlining, which is brought into scope as
+/// `alias` if it has one, as in `..std.(show to_string)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineName {
+    pub alias: Option<Ident>,
+    pub name: Ident,
+}
+
+impl InlineName {
+    /// Returns the name the field is brought into scope as
+    pub fn scoped(&self) -> &Ident {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+/// A function declared as `Type:name(params) body`, whose body may refer to
+/// the fields and methods of its receiver without naming it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method<'a, M: NodeMeta> {
+    pub receiver: Ident,
+    pub is_mut: bool,
+    pub name: Ident,
+    pub func: Func<'a, M>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NamedParam<'a, M: NodeMeta> {
     pub is_mut: bool,
@@ -66,6 +114,7 @@ pub enum Expr<'a, M: NodeMeta> {
     Ident(Ident),
     Prim(Prim),
     Struct(Struct<'a, M>),
+    Enum(Enum<'a, M>),
     Block(Block<'a, M>),
     Unop(Unop<'a, M>),
     Binop(Binop<'a, M>),
@@ -73,6 +122,13 @@ pub enum Expr<'a, M: NodeMeta> {
     Call(Call<'a, M>),
     Constructor(Constructor<'a, M>),
     Project(Project<'a, M>),
+    Template(Template<'a, M>),
+    Generic(Generic<'a, M>),
+    Instantiate(Instantiate<'a, M>),
+    If(If<'a, M>),
+    List(List<'a, M>),
+    ListType(ListType<'a, M>),
+    Match(Match<'a, M>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -100,10 +156,72 @@ pub struct Project<'a, M: NodeMeta> {
     pub field: Ident,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template<'a, M: NodeMeta> {
+    pub parts: Vec<TemplatePart<'a, M>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart<'a, M: NodeMeta> {
+    Text(String),
+    Expr(Node<Expr<'a, M>, M>),
+}
+
+/// A list literal, as in `[1, 2, 3]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct List<'a, M: NodeMeta> {
+    pub items: Vec<Node<Expr<'a, M>, M>>,
+}
+
+/// The type of lists whose items are of type `item`, as in `[]I32`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListType<'a, M: NodeMeta> {
+    pub item: Node<Expr<'a, M>, M>,
+}
+
+/// Evaluates `then` only if `cond` holds, as in `?(cond) { ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If<'a, M: NodeMeta> {
+    pub cond: Node<Expr<'a, M>, M>,
+    pub then: Node<Expr<'a, M>, M>,
+}
+
+/// A declaration that is generic over its parameters, as in `Dyn<T> (...)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generic<'a, M: NodeMeta> {
+    pub params: Vec<Node<GenericParam<'a, M>, M>>,
+    pub expr: Node<Expr<'a, M>, M>,
+}
+
+/// A generic parameter, which is fixed to `value` if it has one, as in
+/// `ToString<Self Vector3>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericParam<'a, M: NodeMeta> {
+    pub ident: Ident,
+    pub value: Option<Node<Expr<'a, M>, M>>,
+}
+
+/// A generic declaration given its arguments, as in `Dyn<ToString>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instantiate<'a, M: NodeMeta> {
+    pub expr: Node<Expr<'a, M>, M>,
+    pub args: Vec<Node<Expr<'a, M>, M>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Func<'a, M: NodeMeta> {
     pub params: Node<Params<'a, M>, M>,
     pub body: Node<Expr<'a, M>, M>,
+    /// Whether it explicitly overrides an earlier definition of the same name,
+    /// as in `add!(2, 2) 5`
+    pub is_override: bool,
+}
+
+/// A function defined by clauses that are tried in order, as in
+/// `add { (2, 2) 5, (x I32, y I32) x + y }`, where each clause is a [Func]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a, M: NodeMeta> {
+    pub clauses: Vec<Node<Expr<'a, M>, M>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -144,11 +262,181 @@ pub struct Update<'a, M: NodeMeta> {
     pub value: Node<Expr<'a, M>, M>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub enum Prim {
+    I8(i8),
+    I16(i16),
     I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
     String(String),
-    Char(u8),
+    Char(char),
+}
+
+/// Compares floats by their bits, so that equality is reflexive even for the
+/// NaNs produced by evaluation
+///
+/// Evaluating `==` compares floats numerically instead.
+impl PartialEq for Prim {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Prim::I8(a), Prim::I8(b)) => a == b,
+            (Prim::I16(a), Prim::I16(b)) => a == b,
+            (Prim::I32(a), Prim::I32(b)) => a == b,
+            (Prim::I64(a), Prim::I64(b)) => a == b,
+            (Prim::U8(a), Prim::U8(b)) => a == b,
+            (Prim::U16(a), Prim::U16(b)) => a == b,
+            (Prim::U32(a), Prim::U32(b)) => a == b,
+            (Prim::U64(a), Prim::U64(b)) => a == b,
+            (Prim::F32(a), Prim::F32(b)) => a.to_bits() == b.to_bits(),
+            (Prim::F64(a), Prim::F64(b)) => a.to_bits() == b.to_bits(),
+            (Prim::String(a), Prim::String(b)) => a == b,
+            (Prim::Char(a), Prim::Char(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Prim {}
+
+impl<'a, M: NodeMeta> Param<'a, M> {
+    /// Returns the literal an unnamed parameter such as the `0` in
+    /// `fibonacci(0)` accepts, if it is one
+    pub fn literal(&self) -> Option<&Node<Expr<'a, M>, M>> {
+        self.expr
+            .as_ref()
+            .filter(|expr| self.ident.is_void && matches!(*expr.get(), Expr::Prim(_)))
+    }
+}
+
+impl Prim {
+    /// Returns the name of the primitive's type, e.g. `"String"`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Prim::I8(_) => "I8",
+            Prim::I16(_) => "I16",
+            Prim::I32(_) => "I32",
+            Prim::I64(_) => "I64",
+            Prim::U8(_) => "U8",
+            Prim::U16(_) => "U16",
+            Prim::U32(_) => "U32",
+            Prim::U64(_) => "U64",
+            Prim::F32(_) => "F32",
+            Prim::F64(_) => "F64",
+            Prim::String(_) => "String",
+            Prim::Char(_) => "Char",
+        }
+    }
+
+    /// Returns `true` if the primitive is a value of the primitive type named
+    /// `ty`, as `42` is a `U16`
+    ///
+    /// Integers are values of every numeric type that can represent them
+    /// exactly.
+    pub fn fits(&self, ty: &str) -> bool {
+        let n = match *self {
+            Prim::I8(n) => i128::from(n),
+            Prim::I16(n) => i128::from(n),
+            Prim::I32(n) => i128::from(n),
+            Prim::I64(n) => i128::from(n),
+            Prim::U8(n) => i128::from(n),
+            Prim::U16(n) => i128::from(n),
+            Prim::U32(n) => i128::from(n),
+            Prim::U64(n) => i128::from(n),
+            _ => return widens(self.type_name(), ty),
+        };
+        match ty {
+            "I8" => i8::try_from(n).is_ok(),
+            "I16" => i16::try_from(n).is_ok(),
+            "I32" => i32::try_from(n).is_ok(),
+            "I64" => i64::try_from(n).is_ok(),
+            "U8" => u8::try_from(n).is_ok(),
+            "U16" => u16::try_from(n).is_ok(),
+            "U32" => u32::try_from(n).is_ok(),
+            "U64" => u64::try_from(n).is_ok(),
+            "F32" => n.unsigned_abs() <= 1 << f32::MANTISSA_DIGITS,
+            "F64" => n.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS,
+            _ => widens(self.type_name(), ty),
+        }
+    }
+
+    /// Converts the primitive to the primitive type named `ty`, or returns
+    /// `None` if it doesn't [`fit`](Prim::fits) that type
+    pub fn cast(&self, ty: &str) -> Option<Prim> {
+        if !self.fits(ty) {
+            return None;
+        }
+        let prim = match *self {
+            Prim::I8(n) => cast_int(i128::from(n), ty),
+            Prim::I16(n) => cast_int(i128::from(n), ty),
+            Prim::I32(n) => cast_int(i128::from(n), ty),
+            Prim::I64(n) => cast_int(i128::from(n), ty),
+            Prim::U8(n) => cast_int(i128::from(n), ty),
+            Prim::U16(n) => cast_int(i128::from(n), ty),
+            Prim::U32(n) => cast_int(i128::from(n), ty),
+            Prim::U64(n) => cast_int(i128::from(n), ty),
+            Prim::F32(n) if ty == "F64" => Prim::F64(f64::from(n)),
+            _ => self.clone(),
+        };
+        Some(prim)
+    }
+}
+
+/// Converts an integer known to fit the primitive type named `ty`
+fn cast_int(n: i128, ty: &str) -> Prim {
+    match ty {
+        "I8" => Prim::I8(n as i8),
+        "I16" => Prim::I16(n as i16),
+        "I32" => Prim::I32(n as i32),
+        "I64" => Prim::I64(n as i64),
+        "U8" => Prim::U8(n as u8),
+        "U16" => Prim::U16(n as u16),
+        "U32" => Prim::U32(n as u32),
+        "U64" => Prim::U64(n as u64),
+        "F32" => Prim::F32(n as f32),
+        _ => Prim::F64(n as f64),
+    }
+}
+
+/// Returns `true` if every value of the primitive type named `from` is also a
+/// value of the one named `to`, as every `U16` is an `I32`
+pub fn widens(from: &str, to: &str) -> bool {
+    from == to
+        || matches!(
+            (from, to),
+            ("I8", "I16" | "I32" | "I64" | "F32" | "F64")
+                | ("I16", "I32" | "I64" | "F32" | "F64")
+                | ("I32", "I64" | "F64")
+                | (
+                    "U8",
+                    "I16" | "I32" | "I64" | "U16" | "U32" | "U64" | "F32" | "F64"
+                )
+                | ("U16", "I32" | "I64" | "U32" | "U64" | "F32" | "F64")
+                | ("U32", "I64" | "U64" | "F64")
+                | ("F32", "F64")
+        )
+}
+
+impl From<Number> for Prim {
+    fn from(number: Number) -> Self {
+        match number {
+            Number::I8(n) => Prim::I8(n),
+            Number::I16(n) => Prim::I16(n),
+            Number::I32(n) => Prim::I32(n),
+            Number::I64(n) => Prim::I64(n),
+            Number::U8(n) => Prim::U8(n),
+            Number::U16(n) => Prim::U16(n),
+            Number::U32(n) => Prim::U32(n),
+            Number::U64(n) => Prim::U64(n),
+            Number::F32(n) => Prim::F32(n),
+            Number::F64(n) => Prim::F64(n),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -157,6 +445,8 @@ pub enum Stmt<'a, M: NodeMeta> {
     BindMut(BindMut<'a, M>),
     Write(Write<'a, M>),
     Update(Update<'a, M>),
+    /// Returns a value from the enclosing function, as in `=> value`
+    Return(Node<Expr<'a, M>, M>),
     Expr(Node<Expr<'a, M>, M>),
     Spacer,
 }
@@ -169,6 +459,78 @@ pub enum BinopKind {
     Div,
     Pow,
     Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinopKind {
+    pub const ALL: [BinopKind; 12] = [
+        BinopKind::Add,
+        BinopKind::Sub,
+        BinopKind::Mul,
+        BinopKind::Div,
+        BinopKind::Pow,
+        BinopKind::Concat,
+        BinopKind::Eq,
+        BinopKind::Ne,
+        BinopKind::Lt,
+        BinopKind::Le,
+        BinopKind::Gt,
+        BinopKind::Ge,
+    ];
+
+    /// Returns the name of the operator as an identifier, e.g. `"(+)"`
+    pub fn ident_name(self) -> &'static str {
+        match self {
+            BinopKind::Add => "(+)",
+            BinopKind::Sub => "(-)",
+            BinopKind::Mul => "(*)",
+            BinopKind::Div => "(/)",
+            BinopKind::Pow => "(^)",
+            BinopKind::Concat => "(++)",
+            BinopKind::Eq => "(==)",
+            BinopKind::Ne => "(!=)",
+            BinopKind::Lt => "(<)",
+            BinopKind::Le => "(<=)",
+            BinopKind::Gt => "(>)",
+            BinopKind::Ge => "(>=)",
+        }
+    }
+
+    /// Returns the operator an identifier such as `(+)` names, if any
+    pub fn from_ident_name(name: &str) -> Option<BinopKind> {
+        BinopKind::ALL
+            .into_iter()
+            .find(|op| op.ident_name() == name)
+    }
+
+    /// Returns how tightly the operator binds, where higher binds tighter
+    ///
+    /// Unary operators bind tighter than every binary operator except
+    /// [BinopKind::Pow], so `-x^2` is `-(x^2)`.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinopKind::Eq
+            | BinopKind::Ne
+            | BinopKind::Lt
+            | BinopKind::Le
+            | BinopKind::Gt
+            | BinopKind::Ge => 0,
+            BinopKind::Concat => 1,
+            BinopKind::Add | BinopKind::Sub => 2,
+            BinopKind::Mul | BinopKind::Div => 3,
+            BinopKind::Pow => 5,
+        }
+    }
+
+    /// Returns `true` if `a op b op c` groups as `a op (b op c)`
+    pub fn is_right_assoc(self) -> bool {
+        matches!(self, BinopKind::Pow)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
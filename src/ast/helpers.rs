@@ -26,12 +26,42 @@ pub fn estruct<'a, M: NodeMeta>(
     )
 }
 
+pub fn eenum<'a, M: NodeMeta>(
+    variants: impl Into<Vec<Node<Field<'a, M>, M>>>,
+) -> Node<Expr<'a, M>, M> {
+    Node::new(
+        Expr::Enum(Enum {
+            variants: variants.into(),
+        }),
+        M::default(),
+    )
+}
+
 pub fn estring<'a, M: NodeMeta>(string: impl Into<String>) -> Node<Expr<'a, M>, M> {
     Node::new(Expr::Prim(Prim::String(string.into())), M::default())
 }
 
+pub fn etemplate<'a, M: NodeMeta>(
+    parts: impl Into<Vec<TemplatePart<'a, M>>>,
+) -> Node<Expr<'a, M>, M> {
+    Node::new(
+        Expr::Template(Template {
+            parts: parts.into(),
+        }),
+        M::default(),
+    )
+}
+
+pub fn ttext<'a, M: NodeMeta>(text: impl Into<String>) -> TemplatePart<'a, M> {
+    TemplatePart::Text(text.into())
+}
+
+pub fn texpr<'a, M: NodeMeta>(expr: Node<Expr<'a, M>, M>) -> TemplatePart<'a, M> {
+    TemplatePart::Expr(expr)
+}
+
 pub fn echar<'a, M: NodeMeta>(char: char) -> Node<Expr<'a, M>, M> {
-    Node::new(Expr::Prim(Prim::Char(char as u8)), M::default())
+    Node::new(Expr::Prim(Prim::Char(char)), M::default())
 }
 
 pub fn istruct<'a, M: NodeMeta>(
@@ -49,6 +79,52 @@ pub fn field<'a, M: NodeMeta>(ident: Ident, expr: Node<Expr<'a, M>, M>) -> Node<
     Node::new(Field::Field(ident, expr), M::default())
 }
 
+pub fn method<'a, M: NodeMeta>(
+    receiver: Ident,
+    name: Ident,
+    params: Node<Params<'a, M>, M>,
+    body: Node<Expr<'a, M>, M>,
+) -> Node<Field<'a, M>, M> {
+    Node::new(
+        Field::Method(Method {
+            receiver,
+            is_mut: false,
+            name,
+            func: Func {
+                params,
+                body,
+                is_override: false,
+            },
+        }),
+        M::default(),
+    )
+}
+
+pub fn method_mut<'a, M: NodeMeta>(
+    receiver: Ident,
+    name: Ident,
+    params: Node<Params<'a, M>, M>,
+    body: Node<Expr<'a, M>, M>,
+) -> Node<Field<'a, M>, M> {
+    Node::new(
+        Field::Method(Method {
+            receiver,
+            is_mut: true,
+            name,
+            func: Func {
+                params,
+                body,
+                is_override: false,
+            },
+        }),
+        M::default(),
+    )
+}
+
+pub fn fdoc<'a, M: NodeMeta>(text: &str) -> Node<Field<'a, M>, M> {
+    Node::new(Field::Doc(text.into()), M::default())
+}
+
 pub fn fspacer<'a, M: NodeMeta>() -> Node<Field<'a, M>, M> {
     Node::new(Field::Spacer, M::default())
 }
@@ -238,7 +314,70 @@ pub fn efunc<'a, M: NodeMeta>(
     params: Node<Params<'a, M>, M>,
     body: Node<Expr<'a, M>, M>,
 ) -> Node<Expr<'a, M>, M> {
-    Node::new(Expr::Func(Func { params, body }), M::default())
+    Node::new(
+        Expr::Func(Func {
+            params,
+            body,
+            is_override: false,
+        }),
+        M::default(),
+    )
+}
+
+pub fn elist<'a, M: NodeMeta>(items: impl Into<Vec<Node<Expr<'a, M>, M>>>) -> Node<Expr<'a, M>, M> {
+    Node::new(
+        Expr::List(List {
+            items: items.into(),
+        }),
+        M::default(),
+    )
+}
+
+pub fn elisttype<'a, M: NodeMeta>(item: Node<Expr<'a, M>, M>) -> Node<Expr<'a, M>, M> {
+    Node::new(Expr::ListType(ListType { item }), M::default())
+}
+
+pub fn egeneric<'a, M: NodeMeta>(
+    params: impl Into<Vec<Node<GenericParam<'a, M>, M>>>,
+    expr: Node<Expr<'a, M>, M>,
+) -> Node<Expr<'a, M>, M> {
+    Node::new(
+        Expr::Generic(Generic {
+            params: params.into(),
+            expr,
+        }),
+        M::default(),
+    )
+}
+
+pub fn gparam<'a, M: NodeMeta>(ident: Ident) -> Node<GenericParam<'a, M>, M> {
+    Node::new(GenericParam { ident, value: None }, M::default())
+}
+
+pub fn gparam_value<'a, M: NodeMeta>(
+    ident: Ident,
+    value: Node<Expr<'a, M>, M>,
+) -> Node<GenericParam<'a, M>, M> {
+    Node::new(
+        GenericParam {
+            ident,
+            value: Some(value),
+        },
+        M::default(),
+    )
+}
+
+pub fn einstantiate<'a, M: NodeMeta>(
+    expr: Node<Expr<'a, M>, M>,
+    args: impl Into<Vec<Node<Expr<'a, M>, M>>>,
+) -> Node<Expr<'a, M>, M> {
+    Node::new(
+        Expr::Instantiate(Instantiate {
+            expr,
+            args: args.into(),
+        }),
+        M::default(),
+    )
 }
 
 pub fn eproj<'a, M: NodeMeta>(expr: Node<Expr<'a, M>, M>, field: Ident) -> Node<Expr<'a, M>, M> {
@@ -302,6 +441,18 @@ pub fn eparams<'a, M: NodeMeta>(
 pub fn arg<'a, M: NodeMeta>(expr: Node<Expr<'a, M>, M>) -> Node<Arg<'a, M>, M> {
     Node::new(
         Arg {
+            name: None,
+            expr,
+            is_mut: false,
+        },
+        M::default(),
+    )
+}
+
+pub fn arg_named<'a, M: NodeMeta>(name: Ident, expr: Node<Expr<'a, M>, M>) -> Node<Arg<'a, M>, M> {
+    Node::new(
+        Arg {
+            name: Some(name),
             expr,
             is_mut: false,
         },
@@ -313,15 +464,51 @@ pub fn param<'a, M: NodeMeta>(ident: Ident, expr: Node<Expr<'a, M>, M>) -> Node<
     Node::new(
         Param {
             ident,
-            expr,
+            expr: Some(expr),
+            is_mut: false,
+            default: None,
+        },
+        M::default(),
+    )
+}
+
+pub fn param_untyped<'a, M: NodeMeta>(ident: Ident) -> Node<Param<'a, M>, M> {
+    Node::new(
+        Param {
+            ident,
+            expr: None,
+            is_mut: false,
+            default: None,
+        },
+        M::default(),
+    )
+}
+
+pub fn param_default<'a, M: NodeMeta>(
+    ident: Ident,
+    expr: Node<Expr<'a, M>, M>,
+    default: Node<Expr<'a, M>, M>,
+) -> Node<Param<'a, M>, M> {
+    Node::new(
+        Param {
+            ident,
+            expr: Some(expr),
             is_mut: false,
+            default: Some(default),
         },
         M::default(),
     )
 }
 
 pub fn arg_mut<'a, M: NodeMeta>(expr: Node<Expr<'a, M>, M>) -> Node<Arg<'a, M>, M> {
-    Node::new(Arg { expr, is_mut: true }, M::default())
+    Node::new(
+        Arg {
+            name: None,
+            expr,
+            is_mut: true,
+        },
+        M::default(),
+    )
 }
 
 pub fn param_mut<'a, M: NodeMeta>(
@@ -331,8 +518,9 @@ pub fn param_mut<'a, M: NodeMeta>(
     Node::new(
         Param {
             ident,
-            expr,
+            expr: Some(expr),
             is_mut: true,
+            default: None,
         },
         M::default(),
     )
@@ -12,6 +12,9 @@ pub struct PrettyPrintContext<'parent> {
     pub expand: bool,
     pub max_width: usize,
     pub colors: bool,
+    /// Whether method calls without arguments are printed as `x:m` rather
+    /// than `x:m()`
+    pub omit_empty_parens: bool,
     pub cs: Colorscheme,
 }
 
@@ -23,6 +26,7 @@ impl Default for PrettyPrintContext<'_> {
             expand: false,
             max_width: 80,
             colors: true,
+            omit_empty_parens: false,
             cs: Colorscheme::default(),
         }
     }
@@ -52,20 +56,27 @@ impl<'parent> PrettyPrintContext<'parent> {
         self.argumets.contains(argument)
     }
 
-    pub fn with_expand(&self, expand: bool) -> PrettyPrintContext {
+    pub fn with_expand(&self, expand: bool) -> PrettyPrintContext<'_> {
         PrettyPrintContext {
             expand,
             ..self.clone()
         }
     }
 
-    pub fn with_colors(&self, colors: bool) -> PrettyPrintContext {
+    pub fn with_colors(&self, colors: bool) -> PrettyPrintContext<'_> {
         PrettyPrintContext {
             colors,
             ..self.clone()
         }
     }
 
+    pub fn with_omit_empty_parens(&self, omit_empty_parens: bool) -> PrettyPrintContext<'_> {
+        PrettyPrintContext {
+            omit_empty_parens,
+            ..self.clone()
+        }
+    }
+
     pub fn color(&self, str: impl Into<String>, color: Color) -> String {
         if self.colors {
             format!("{}", str.into().color(color))
@@ -97,53 +108,97 @@ pub trait PrettyPrint {
 
 impl<'a, M: NodeMeta> PrettyPrint for Struct<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
-        if self.fields.is_empty() {
-            return ctxt.color("()", ctxt.cs.punctuation);
-        }
-        if self.fields.len() == 1 {
-            let field = &self.fields[0].get();
-            let mut is_large = false;
-            match &**field {
-                Field::Inline(expr) => {
-                    if let Expr::Struct(_) | Expr::Block(_) = expr {
-                        is_large = true;
-                    }
-                }
-                Field::Field(_, expr) => {
-                    if let Expr::Struct(_) | Expr::Block(_) = &*expr.get() {
-                        is_large = true;
-                    }
+        print_fields(&self.fields, "(", ")", ctxt)
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Enum<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        print_fields(&self.variants, "<", ">", ctxt)
+    }
+}
+
+/// Prints the fields of a struct or the variants of an enum between `open`
+/// and `close`, one per line if the context is expanded
+fn print_fields<M: NodeMeta>(
+    fields: &[Node<Field<'_, M>, M>],
+    open: &str,
+    close: &str,
+    ctxt: &mut PrettyPrintContext,
+) -> String {
+    if fields.is_empty() {
+        return ctxt.color(format!("{open}{close}"), ctxt.cs.punctuation);
+    }
+    if fields.len() == 1 {
+        let field = &fields[0].get();
+        let mut is_large = false;
+        match &**field {
+            Field::Inline(expr) => {
+                if let Expr::Struct(_) | Expr::Block(_) | Expr::Match(_) = expr {
+                    is_large = true;
                 }
-                Field::Spacer => {}
             }
-            if !is_large {
-                return ctxt.color("(", ctxt.cs.punctuation)
-                    + &field.pretty_print(ctxt)
-                    + &ctxt.color(")", ctxt.cs.punctuation);
+            Field::Field(_, expr) => {
+                if let Expr::Struct(_) | Expr::Block(_) | Expr::Match(_) = &*expr.get() {
+                    is_large = true;
+                }
             }
+            Field::PartialInline(_) | Field::Method(_) | Field::Doc(_) | Field::Spacer => {}
         }
-        let mut s = String::new();
-        s += &ctxt.color("(", ctxt.cs.punctuation);
-        if ctxt.expand {
-            s += "\n";
+        if !is_large {
+            return ctxt.color(open, ctxt.cs.punctuation)
+                + &field.pretty_print(ctxt)
+                + &ctxt.color(close, ctxt.cs.punctuation);
         }
-        for i in 0..self.fields.len() {
-            if ctxt.expand {
-                s += &ctxt.indented().indent();
-            }
-            s += &self.fields[i].get().pretty_print(ctxt);
-            if ctxt.expand {
-                s += "\n";
-            } else if i < self.fields.len() - 1 {
-                s += &ctxt.color(",", ctxt.cs.punctuation);
-                s += " ";
-            }
+    }
+    let mut s = String::new();
+    s += &ctxt.color(open, ctxt.cs.punctuation);
+    if ctxt.expand {
+        s += "\n";
+    }
+    for i in 0..fields.len() {
+        if ctxt.expand {
+            s += &ctxt.indented().indent();
         }
+        s += &fields[i].get().pretty_print(ctxt);
         if ctxt.expand {
-            s += &ctxt.indent();
+            s += "\n";
+        } else if i < fields.len() - 1 {
+            s += &ctxt.color(",", ctxt.cs.punctuation);
+            s += " ";
         }
-        s += &ctxt.color(")", ctxt.cs.punctuation);
-        s
+    }
+    if ctxt.expand {
+        s += &ctxt.indent();
+    }
+    s += &ctxt.color(close, ctxt.cs.punctuation);
+    s
+}
+
+/// Prints fields with [print_fields], expanding them if they contain spacers
+/// or doc comments, or do not fit on one line
+fn print_fields_fitted<M: NodeMeta>(
+    fields: &[Node<Field<'_, M>, M>],
+    open: &str,
+    close: &str,
+    ctxt: &mut PrettyPrintContext,
+) -> String {
+    let res = print_fields(
+        fields,
+        open,
+        close,
+        &mut ctxt.with_expand(false).with_colors(false),
+    );
+    let has_layout = fields
+        .iter()
+        .any(|field| matches!(*field.get(), Field::Doc(_) | Field::Spacer));
+    if has_layout
+        || res.contains('\n')
+        || res.lines().map(|s| s.chars().count()).max().unwrap_or(0) > ctxt.max_width
+    {
+        print_fields(fields, open, close, &mut ctxt.with_expand(true))
+    } else {
+        print_fields(fields, open, close, &mut ctxt.with_expand(false))
     }
 }
 
@@ -187,15 +242,50 @@ impl<'a, M: NodeMeta> PrettyPrint for Field<'a, M> {
                 (match ident.is_type {
                     true => ctxt.style(&*ident.name, ctxt.cs.member, true, false),
                     false => ctxt.color(&*ident.name, ctxt.cs.member),
-                } + (if let Expr::Func(_) = *expr.get() {
-                    ""
-                } else {
-                    " "
-                }) + &expr.get().pretty_print(&mut ctxt.indented()))
+                } + (if is_adjacent(&expr.get()) { "" } else { " " })
+                    + &expr.get().pretty_print(&mut ctxt.indented()))
             }
             Field::Inline(expr) => {
                 ctxt.color("..", ctxt.cs.punctuation) + &expr.pretty_print(&mut ctxt.indented())
             }
+            Field::PartialInline(partial) => {
+                let names: Vec<_> = partial
+                    .names
+                    .iter()
+                    .map(|name| {
+                        let name = name.get();
+                        let original = ctxt.color(&*name.name.name, ctxt.cs.member);
+                        match &name.alias {
+                            Some(alias) => {
+                                ctxt.color(&*alias.name, ctxt.cs.member) + " " + &original
+                            }
+                            None => original,
+                        }
+                    })
+                    .collect();
+                ctxt.color("..", ctxt.cs.punctuation)
+                    + &partial.expr.get().pretty_print(&mut ctxt.indented())
+                    + &ctxt.color(".(", ctxt.cs.punctuation)
+                    + &names.join(&(ctxt.color(",", ctxt.cs.punctuation) + " "))
+                    + &ctxt.color(")", ctxt.cs.punctuation)
+            }
+            Field::Method(method) => {
+                ctxt.color(if method.is_mut { "*" } else { "" }, ctxt.cs.punctuation)
+                    + &method.receiver.pretty_print(ctxt)
+                    + &ctxt.color(":", ctxt.cs.punctuation)
+                    + &ctxt.color(&*method.name.name, ctxt.cs.member)
+                    + &method.func.pretty_print(&mut ctxt.indented())
+            }
+            Field::Doc(text) => {
+                let lines: Vec<_> = text
+                    .lines()
+                    .map(|line| match line.is_empty() {
+                        true => ctxt.color("---", ctxt.cs.punctuation),
+                        false => ctxt.color(format!("--- {line}"), ctxt.cs.punctuation),
+                    })
+                    .collect();
+                lines.join(&format!("\n{}", ctxt.indented().indent()))
+            }
             Field::Spacer => "".to_string(),
         }
     }
@@ -203,21 +293,41 @@ impl<'a, M: NodeMeta> PrettyPrint for Field<'a, M> {
 
 impl<'a, M: NodeMeta> PrettyPrint for Arg<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
-        ctxt.color(if self.is_mut { "*" } else { "" }, ctxt.cs.punctuation)
+        let name = match &self.name {
+            Some(name) => {
+                ctxt.color(&*name.name, ctxt.cs.member) + &ctxt.color("=", ctxt.cs.operator)
+            }
+            None => String::new(),
+        };
+        name + &ctxt.color(if self.is_mut { "*" } else { "" }, ctxt.cs.punctuation)
             + &self.expr.get().pretty_print(&mut ctxt.indented())
     }
 }
 
 impl<'a, M: NodeMeta> PrettyPrint for Param<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        if let Some(literal) = self.literal() {
+            return literal.get().pretty_print(ctxt);
+        }
         ctxt.add_argument(self.ident.name.as_ref().to_owned());
         ctxt.color(if self.is_mut { "*" } else { "" }, ctxt.cs.punctuation)
             + &match self.ident.is_type {
                 true => ctxt.style(&*self.ident.name, ctxt.cs.normal, true, true),
                 false => ctxt.style(&*self.ident.name, ctxt.cs.normal, false, true),
             }
-            + " "
-            + &self.expr.get().pretty_print(&mut ctxt.indented())
+            + &match &self.expr {
+                Some(expr) => " ".to_owned() + &expr.get().pretty_print(&mut ctxt.indented()),
+                None => String::new(),
+            }
+            + &match &self.default {
+                Some(default) => {
+                    " ".to_owned()
+                        + &ctxt.color("=", ctxt.cs.operator)
+                        + " "
+                        + &default.get().pretty_print(&mut ctxt.indented())
+                }
+                None => String::new(),
+            }
     }
 }
 
@@ -238,26 +348,43 @@ impl<'a, M: NodeMeta> PrettyPrint for Expr<'a, M> {
         match self {
             Expr::Ident(ident) => ident.pretty_print(ctxt),
             Expr::Prim(value) => value.pretty_print(ctxt),
-            Expr::Struct(struct_) => {
-                let res = struct_.pretty_print(&mut ctxt.with_expand(false).with_colors(false));
-                if res.lines().map(|s| s.chars().count()).max().unwrap_or(0) > ctxt.max_width {
-                    struct_.pretty_print(&mut ctxt.with_expand(true))
-                } else {
-                    struct_.pretty_print(&mut ctxt.with_expand(false))
-                }
-            }
+            Expr::Struct(struct_) => print_fields_fitted(&struct_.fields, "(", ")", ctxt),
+            Expr::Enum(enum_) => print_fields_fitted(&enum_.variants, "<", ">", ctxt),
             Expr::Block(block) => block.pretty_print(ctxt),
-            Expr::Unop(unop) => format!(
-                "{}{}",
-                unop.op.pretty_print(ctxt),
-                unop.expr.get().pretty_print(ctxt)
-            ),
-            Expr::Binop(binop) => format!(
-                "{} {} {}",
-                binop.lhs.get().pretty_print(ctxt),
-                binop.op.pretty_print(ctxt),
-                binop.rhs.get().pretty_print(ctxt)
-            ),
+            Expr::Unop(unop) => {
+                let expr = match &*unop.expr.get() {
+                    Expr::Binop(binop) if binop.op.precedence() < BinopKind::Pow.precedence() => {
+                        parenthesize(&unop.expr.get(), ctxt)
+                    }
+                    expr => expr.pretty_print(ctxt),
+                };
+                unop.op.pretty_print(ctxt) + &expr
+            }
+            Expr::Binop(binop) => {
+                let prec = binop.op.precedence();
+                let lhs = match &*binop.lhs.get() {
+                    Expr::Binop(lhs)
+                        if lhs.op.precedence() < prec
+                            || (lhs.op.precedence() == prec && binop.op.is_right_assoc()) =>
+                    {
+                        parenthesize(&binop.lhs.get(), ctxt)
+                    }
+                    Expr::Unop(_) if binop.op == BinopKind::Pow => {
+                        parenthesize(&binop.lhs.get(), ctxt)
+                    }
+                    lhs => lhs.pretty_print(ctxt),
+                };
+                let rhs = match &*binop.rhs.get() {
+                    Expr::Binop(rhs)
+                        if rhs.op.precedence() < prec
+                            || (rhs.op.precedence() == prec && !binop.op.is_right_assoc()) =>
+                    {
+                        parenthesize(&binop.rhs.get(), ctxt)
+                    }
+                    rhs => rhs.pretty_print(ctxt),
+                };
+                format!("{} {} {}", lhs, binop.op.pretty_print(ctxt), rhs)
+            }
             Expr::Func(func) => func.pretty_print(ctxt),
             Expr::Call(call) => call.pretty_print(ctxt),
             Expr::Constructor(constructor) => {
@@ -275,40 +402,135 @@ impl<'a, M: NodeMeta> PrettyPrint for Expr<'a, M> {
                 ctxt.color("..", ctxt.cs.punctuation) + &constructor.name.pretty_print(ctxt) + &res
             }
             Expr::Project(project) => project.pretty_print(ctxt),
+            Expr::Template(template) => template.pretty_print(ctxt),
+            Expr::Generic(generic) => generic.pretty_print(ctxt),
+            Expr::Instantiate(instantiate) => instantiate.pretty_print(ctxt),
+            Expr::List(list) => {
+                let res = list.pretty_print(&mut ctxt.with_expand(false).with_colors(false));
+                if res.contains('\n')
+                    || res.lines().map(|s| s.chars().count()).max().unwrap_or(0) > ctxt.max_width
+                {
+                    list.pretty_print(&mut ctxt.with_expand(true))
+                } else {
+                    list.pretty_print(&mut ctxt.with_expand(false))
+                }
+            }
+            Expr::ListType(list_type) => {
+                ctxt.color("[]", ctxt.cs.punctuation) + &list_type.item.get().pretty_print(ctxt)
+            }
+            Expr::If(if_) => {
+                ctxt.color("?(", ctxt.cs.operator)
+                    + &if_.cond.get().pretty_print(ctxt)
+                    + &ctxt.color(")", ctxt.cs.operator)
+                    + " "
+                    + &if_.then.get().pretty_print(ctxt)
+            }
+            Expr::Match(match_) => match_.pretty_print(ctxt),
+        }
+    }
+}
+
+/// Returns `true` if an expression is printed directly after the name of the
+/// field it is the value of, as in `f(x I32) x` or `Dyn<T> (...)`
+fn is_adjacent<M: NodeMeta>(expr: &Expr<'_, M>) -> bool {
+    matches!(expr, Expr::Func(_) | Expr::Generic(_))
+}
+
+/// Prints an expression wrapped in parentheses
+fn parenthesize<M: NodeMeta>(expr: &Expr<'_, M>, ctxt: &mut PrettyPrintContext) -> String {
+    ctxt.color("(", ctxt.cs.punctuation)
+        + &expr.pretty_print(ctxt)
+        + &ctxt.color(")", ctxt.cs.punctuation)
+}
+
+/// Prints an expression that is followed by a postfix operator, such as the
+/// receiver of a method call or the target of a projection
+fn operand<M: NodeMeta>(expr: &Expr<'_, M>, ctxt: &mut PrettyPrintContext) -> String {
+    match expr {
+        Expr::Unop(_) | Expr::Binop(_) | Expr::Func(_) | Expr::Generic(_) | Expr::If(_) => {
+            parenthesize(expr, ctxt)
         }
+        _ => expr.pretty_print(ctxt),
     }
 }
 
 impl PrettyPrint for Prim {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
         match self {
+            Prim::I8(n) => ctxt.color(format!("{n}i8"), ctxt.cs.constant),
+            Prim::I16(n) => ctxt.color(format!("{n}i16"), ctxt.cs.constant),
             Prim::I32(n) => ctxt.color(n.to_string(), ctxt.cs.constant),
+            Prim::I64(n) => ctxt.color(format!("{n}i64"), ctxt.cs.constant),
+            Prim::U8(n) => ctxt.color(format!("{n}u8"), ctxt.cs.constant),
+            Prim::U16(n) => ctxt.color(format!("{n}u16"), ctxt.cs.constant),
+            Prim::U32(n) => ctxt.color(format!("{n}u32"), ctxt.cs.constant),
+            Prim::U64(n) => ctxt.color(format!("{n}u64"), ctxt.cs.constant),
+            Prim::F32(n) => ctxt.color(format!("{n:?}"), ctxt.cs.constant),
+            Prim::F64(n) => ctxt.color(format!("{n:?}f64"), ctxt.cs.constant),
             Prim::String(s) => {
-                let mut res = String::new();
-                res += &ctxt.color("\"", ctxt.cs.string);
-                let parts: Vec<&str> = s.split("\"").collect();
-                for i in 0..parts.len() {
-                    res += &ctxt.color(parts[i], ctxt.cs.string);
-                    if i < parts.len() - 1 {
-                        res += &ctxt.color("\\\"", ctxt.cs.function);
-                    }
-                }
-                res += &ctxt.color("\"", ctxt.cs.string);
-                res
+                ctxt.color("\"", ctxt.cs.string)
+                    + &escape(s, '"', ctxt)
+                    + &ctxt.color("\"", ctxt.cs.string)
             }
             Prim::Char(c) => {
-                let c = *c as char;
                 ctxt.color("'", ctxt.cs.string)
-                    + &match c {
-                        '\'' => ctxt.color("\\\'", ctxt.cs.function),
-                        _ => ctxt.color(c.to_string(), ctxt.cs.string),
-                    }
+                    + &escape(&c.to_string(), '\'', ctxt)
                     + &ctxt.color("'", ctxt.cs.string)
             }
         }
     }
 }
 
+impl<'a, M: NodeMeta> PrettyPrint for Template<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        let mut s = ctxt.color("\"", ctxt.cs.string);
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => s += &escape(text, '"', ctxt),
+                TemplatePart::Expr(expr) => {
+                    s += &ctxt.color("{", ctxt.cs.punctuation);
+                    s += &expr.get().pretty_print(ctxt);
+                    s += &ctxt.color("}", ctxt.cs.punctuation);
+                }
+            }
+        }
+        s + &ctxt.color("\"", ctxt.cs.string)
+    }
+}
+
+/// Prints the contents of a string or character literal delimited by `quote`,
+/// escaping every character the literal decoder would not read back verbatim
+///
+/// Braces are doubled in strings so that they are not read as interpolations.
+pub fn escape(s: &str, quote: char, ctxt: &PrettyPrintContext) -> String {
+    let mut res = String::new();
+    let mut run = String::new();
+    for c in s.chars() {
+        let escaped = match c {
+            '\\' => "\\\\".to_owned(),
+            '\n' => "\\n".to_owned(),
+            '\r' => "\\r".to_owned(),
+            '\t' => "\\t".to_owned(),
+            '\0' => "\\0".to_owned(),
+            c if c == quote => format!("\\{c}"),
+            '{' | '}' if quote == '"' => format!("{c}{c}"),
+            c if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+            c => {
+                run.push(c);
+                continue;
+            }
+        };
+        if !run.is_empty() {
+            res += &ctxt.color(std::mem::take(&mut run), ctxt.cs.string);
+        }
+        res += &ctxt.color(escaped, ctxt.cs.function);
+    }
+    if !run.is_empty() {
+        res += &ctxt.color(run, ctxt.cs.string);
+    }
+    res
+}
+
 impl<'a, M: NodeMeta> PrettyPrint for Block<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
         if self.stmts.is_empty() {
@@ -353,6 +575,12 @@ impl PrettyPrint for BinopKind {
             BinopKind::Div => ctxt.color("/", ctxt.cs.operator),
             BinopKind::Pow => ctxt.color("^", ctxt.cs.operator),
             BinopKind::Concat => ctxt.color("++", ctxt.cs.operator),
+            BinopKind::Eq => ctxt.color("==", ctxt.cs.operator),
+            BinopKind::Ne => ctxt.color("!=", ctxt.cs.operator),
+            BinopKind::Lt => ctxt.color("<", ctxt.cs.operator),
+            BinopKind::Le => ctxt.color("<=", ctxt.cs.operator),
+            BinopKind::Gt => ctxt.color(">", ctxt.cs.operator),
+            BinopKind::Ge => ctxt.color(">=", ctxt.cs.operator),
         }
     }
 }
@@ -396,6 +624,9 @@ impl<'a, M: NodeMeta> PrettyPrint for Stmt<'a, M> {
                     + " "
                     + &update.value.get().pretty_print(ctxt)
             }
+            Stmt::Return(expr) => {
+                ctxt.color("=>", ctxt.cs.operator) + " " + &expr.get().pretty_print(ctxt)
+            }
             Stmt::Expr(expr) => expr.get().pretty_print(ctxt).to_string(),
             Stmt::Spacer => "".to_owned(),
         }
@@ -404,7 +635,98 @@ impl<'a, M: NodeMeta> PrettyPrint for Stmt<'a, M> {
 
 impl<'a, M: NodeMeta> PrettyPrint for Func<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
-        self.params.get().pretty_print(ctxt) + " " + &self.body.get().pretty_print(ctxt)
+        ctxt.color(if self.is_override { "!" } else { "" }, ctxt.cs.operator)
+            + &self.params.get().pretty_print(ctxt)
+            + " "
+            + &self.body.get().pretty_print(ctxt)
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Match<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        if self.clauses.len() == 1 {
+            return ctxt.color("{", ctxt.cs.punctuation)
+                + " "
+                + &self.clauses[0].get().pretty_print(ctxt)
+                + " "
+                + &ctxt.color("}", ctxt.cs.punctuation);
+        }
+        let mut s = String::new();
+        s += &ctxt.color("{", ctxt.cs.punctuation);
+        s += "\n";
+        for clause in &self.clauses {
+            s += &ctxt.indented().indent();
+            s += &clause.get().pretty_print(ctxt);
+            s += "\n";
+        }
+        s += &ctxt.indent();
+        s += &ctxt.color("}", ctxt.cs.punctuation);
+        s
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for List<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        if self.items.is_empty() {
+            return ctxt.color("[]", ctxt.cs.punctuation);
+        }
+        let mut s = ctxt.color("[", ctxt.cs.punctuation);
+        if ctxt.expand {
+            s += "\n";
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            if ctxt.expand {
+                s += &ctxt.indented().indent();
+            }
+            s += &item.get().pretty_print(&mut ctxt.indented());
+            if ctxt.expand {
+                s += "\n";
+            } else if i < self.items.len() - 1 {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+                s += " ";
+            }
+        }
+        if ctxt.expand {
+            s += &ctxt.indent();
+        }
+        s + &ctxt.color("]", ctxt.cs.punctuation)
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Generic<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        let mut s = ctxt.color("<", ctxt.cs.punctuation);
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+                s += " ";
+            }
+            let param = param.get();
+            s += &param.ident.pretty_print(ctxt);
+            if let Some(value) = &param.value {
+                s += " ";
+                s += &value.get().pretty_print(ctxt);
+            }
+        }
+        s += &ctxt.color(">", ctxt.cs.punctuation);
+        if !matches!(*self.expr.get(), Expr::Func(_)) {
+            s += " ";
+        }
+        s + &self.expr.get().pretty_print(ctxt)
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Instantiate<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        let mut s = operand(&self.expr.get(), ctxt) + &ctxt.color("<", ctxt.cs.punctuation);
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+                s += " ";
+            }
+            s += &arg.get().pretty_print(ctxt);
+        }
+        s + &ctxt.color(">", ctxt.cs.punctuation)
     }
 }
 
@@ -413,13 +735,17 @@ impl<'a, M: NodeMeta> PrettyPrint for Call<'a, M> {
         let mut s = String::new();
         if self.method_syntax {
             assert!(!self.args.get().args.is_empty());
-            s += &self.args.get().args[0].get().pretty_print(ctxt);
+            let receiver = &self.args.get().args[0];
+            let receiver = receiver.get();
+            s += &ctxt.color(if receiver.is_mut { "*" } else { "" }, ctxt.cs.punctuation);
+            s += &operand(&receiver.expr.get(), ctxt);
             s += &ctxt.color(":", ctxt.cs.punctuation);
         }
         let mut func = self.func.get().clone();
         loop {
             match &func {
                 Expr::Ident(ident) => {
+                    s += &ctxt.color("^".repeat(ident.nshadow), ctxt.cs.punctuation);
                     s += &if ident.is_type {
                         ctxt.style(&*ident.name, ctxt.cs.type_, true, false)
                     } else {
@@ -428,17 +754,20 @@ impl<'a, M: NodeMeta> PrettyPrint for Call<'a, M> {
                     break;
                 }
                 Expr::Project(Project { expr, field }) => {
-                    s += &expr.get().pretty_print(ctxt).to_string();
+                    s += &operand(&expr.get(), ctxt);
                     s += &ctxt.color(".", ctxt.cs.punctuation);
                     func = Expr::Ident(field.clone());
                     continue;
                 }
                 _ => {
-                    s += &self.func.get().pretty_print(ctxt);
+                    s += &operand(&func, ctxt);
                     break;
                 }
             }
         }
+        if self.method_syntax && ctxt.omit_empty_parens && self.args.get().args.len() == 1 {
+            return s;
+        }
         s += &ctxt.color("(", ctxt.cs.operator);
         let start_idx = if self.method_syntax { 1 } else { 0 };
         for i in start_idx..self.args.get().args.len() {
@@ -455,7 +784,7 @@ impl<'a, M: NodeMeta> PrettyPrint for Call<'a, M> {
 
 impl<'a, M: NodeMeta> PrettyPrint for Project<'a, M> {
     fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
-        self.expr.get().pretty_print(ctxt)
+        operand(&self.expr.get(), ctxt)
             + &ctxt.color(".", ctxt.cs.punctuation)
             + &if self.field.is_type {
                 ctxt.style(&*self.field.name, ctxt.cs.type_, true, false)
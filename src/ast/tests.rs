@@ -0,0 +1,100 @@
+use super::helpers::*;
+use super::pretty_print::*;
+use super::*;
+use crate::lexer::{Lexer, Segment, Token, literal};
+
+fn print(expr: &Node<Expr<'_, ()>, ()>) -> String {
+    expr.get()
+        .pretty_print(&mut PrettyPrintContext::default().with_colors(false))
+}
+
+fn relex(source: &str, expected: Token) -> (Token, crate::lexer::Span) {
+    let (tokens, errors) = Lexer::new(source).tokenize();
+    assert_eq!(errors, vec![], "lexing {source}");
+    assert_eq!(tokens.len(), 1, "lexing {source}");
+    assert_eq!(tokens[0].0, expected, "lexing {source}");
+    tokens[0]
+}
+
+#[test]
+fn test_string_round_trip() {
+    let strings = [
+        "",
+        "Hello, world!",
+        "quote \" inside",
+        "back\\slash",
+        "line\nbreak\r\n",
+        "\ttabbed",
+        "nul \0 byte",
+        "bell \u{7} and escape \u{1b}",
+        "caf\u{e9} \u{1f600}",
+        "it's",
+        "\\n is not a newline",
+        "{braces} and {{doubled}} }",
+    ];
+    for string in strings {
+        let printed = print(&estring(string));
+        let (_, span) = relex(&printed, Token::String);
+        assert_eq!(
+            literal::decode_string(&printed, span),
+            Ok(string.to_owned()),
+            "round-tripping {printed}"
+        );
+    }
+}
+
+#[test]
+fn test_char_round_trip() {
+    let chars = [
+        'a',
+        ' ',
+        '"',
+        '\'',
+        '\\',
+        '\n',
+        '\r',
+        '\t',
+        '\0',
+        '\u{7f}',
+        '\u{e9}',
+        '\u{1f600}',
+    ];
+    for c in chars {
+        let printed = print(&echar(c));
+        let (_, span) = relex(&printed, Token::Character);
+        assert_eq!(
+            literal::decode_char(&printed, span),
+            Ok(c),
+            "round-tripping {printed}"
+        );
+    }
+}
+
+#[test]
+fn test_template_pretty_print() {
+    let template = etemplate([
+        ttext("("),
+        texpr(eproj(evid("self"), vid("x"))),
+        ttext(", {y}"),
+        texpr(ecall(evid("f"), args([arg(estring("}"))]))),
+        ttext(")"),
+    ]);
+    let printed = print(&template);
+    assert_eq!(printed, r#""({self.x}, {{y}}{f("}}")})""#);
+
+    let mut lexer = Lexer::new(&printed);
+    assert_eq!(lexer.next().unwrap().unwrap().0, Token::String);
+    let segments = lexer.segments().unwrap();
+    assert_eq!(segments.len(), 5);
+    assert!(matches!(&segments[0], Segment::Text(text) if text == "("));
+    assert!(matches!(&segments[2], Segment::Text(text) if text == ", {y}"));
+    assert!(matches!(&segments[4], Segment::Text(text) if text == ")"));
+    let Segment::Expr(call) = &segments[3] else {
+        panic!("expected an interpolated expression");
+    };
+    let tokens: Vec<Token> = call.clone().map(|tok| tok.unwrap().0).collect();
+    assert_eq!(
+        tokens,
+        vec![Token::Ident, Token::LParen, Token::String, Token::RParen]
+    );
+}
@@ -1,56 +1,1314 @@
-use std::collections::HashMap;
+#[cfg(test)]
+mod tests;
 
+use std::{cell::RefCell, cmp::Ordering, fmt, rc::Rc};
+
+use crate::ast::{BinopKind, Prim, UnopKind};
 use crate::ir::*;
+use crate::node::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Value {
+/// A value computed by evaluating the IR
+#[derive(Debug, Clone)]
+pub enum Value<'a, M: NodeMeta> {
     Prim(Prim),
+    Bool(bool),
+    /// A struct, which is a type if it was declared rather than constructed
+    Struct(StructValue<'a, M>),
+    /// An enum type, holding the type of each variant
+    Enum(Rc<Fields<'a, M>>),
+    /// A value of one of the variants of an enum
+    Variant {
+        name: Rc<str>,
+        value: Box<Value<'a, M>>,
+    },
+    List(Vec<Value<'a, M>>),
+    /// The type of lists whose items are of the given type
+    ListType(Box<Value<'a, M>>),
+    Func(Rc<Closure<'a, M>>),
+    /// Functions sharing a name, called by the first whose parameters accept
+    /// the arguments
+    Dispatch(Rc<Vec<Value<'a, M>>>),
+    Builtin(Builtin),
+}
+
+/// The fields of a struct, along with the struct type it was constructed
+/// from, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructValue<'a, M: NodeMeta> {
+    pub ty: Option<Rc<StructValue<'a, M>>>,
+    pub fields: Fields<'a, M>,
+}
+
+/// Names and their values, in declaration order
+pub type Fields<'a, M> = Vec<(Rc<str>, Value<'a, M>)>;
+
+/// A function along with the environment it was defined in
+pub struct Closure<'a, M: NodeMeta> {
+    pub func: Func<'a, M>,
+    env: Env<'a, M>,
+}
+
+/// A value provided by the runtime rather than defined in source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Builtin {
+    /// A primitive type, which converts its argument to itself
+    Type(Rc<str>),
+    Print,
+    Println,
+    Sqrt,
+    /// Returns the number of items in a list or characters in a string
+    Len,
+    /// Returns the item of a list at an index
+    Get,
+    /// Calls a function on each item of a list, returning a list of the
+    /// results
+    Map,
+    /// Calls a function on each item of a list
+    Each,
+    /// Joins two lists, where `++` would append the second as one item
+    Concat,
+    /// A binary operator on primitives, bound to its operator identifier
+    Op(BinopKind),
+    /// The type of every value, bound to the parameters of a generic
+    /// declaration that are not fixed
+    Any,
+}
+
+/// An error encountered while evaluating the IR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError<M: NodeMeta> {
+    /// A name is not in scope, or is used before its value is computed
+    Unbound {
+        name: Rc<str>,
+        meta: M,
+    },
+    /// A value has no field with the given name
+    NoField {
+        name: Rc<str>,
+        meta: M,
+    },
+    /// A value is not of the kind an operation expects
+    Mismatch {
+        expected: &'static str,
+        meta: M,
+    },
+    /// A value that is not a function, type or variant is called
+    NotCallable {
+        meta: M,
+    },
+    /// No argument is passed to a parameter without a default
+    MissingArg {
+        name: Rc<str>,
+        meta: M,
+    },
+    /// More arguments are passed than there are parameters
+    TooManyArgs {
+        meta: M,
+    },
+    /// An expression that is not a name or a projection is written to
+    NotAssignable {
+        meta: M,
+    },
+    /// A list is indexed outside of its bounds
+    OutOfBounds {
+        index: i32,
+        len: usize,
+        meta: M,
+    },
+    DivideByZero {
+        meta: M,
+    },
+    Overflow {
+        meta: M,
+    },
+    /// A value of an unsigned type is negated
+    NegateUnsigned {
+        meta: M,
+    },
+}
+
+/// Why evaluation stopped before reaching the end of an expression
+enum Exit<'a, M: NodeMeta> {
+    /// A return statement exited the innermost function with a value
+    Return(Value<'a, M>),
+    Error(EvalError<M>),
 }
 
-pub trait Eval {
-    fn eval(&self, ctxt: &mut HashMap<Ident, Value>) -> Result<Option<Value>, &'static str>;
+impl<'a, M: NodeMeta> From<EvalError<M>> for Exit<'a, M> {
+    fn from(error: EvalError<M>) -> Self {
+        Exit::Error(error)
+    }
 }
 
-impl Eval for Block {
-    fn eval(&self, ctxt: &mut HashMap<Ident, Value>) -> Result<Option<Value>, &'static str> {
-        let mut return_value = None;
-        for stmt in &self.stmts {
-            return_value = stmt.eval(ctxt)?;
+/// A name bound in a frame
+struct Slot<'a, M: NodeMeta> {
+    name: Rc<str>,
+    /// The value of the name, or [None] if it is not yet computed
+    value: Option<Value<'a, M>>,
+    /// The type the binding is annotated with, which values written to the
+    /// name must be instances of
+    ty: Option<Value<'a, M>>,
+}
+
+impl<'a, M: NodeMeta> Slot<'a, M> {
+    fn new(name: Rc<str>, value: Option<Value<'a, M>>) -> Self {
+        Slot {
+            name,
+            value,
+            ty: None,
         }
-        Ok(return_value)
     }
 }
 
-impl Eval for Stmt {
-    fn eval(&self, ctxt: &mut HashMap<Ident, Value>) -> Result<Option<Value>, &'static str> {
+/// A set of names and their values
+type Frame<'a, M> = Rc<RefCell<Vec<Slot<'a, M>>>>;
+
+/// The names in scope, innermost last
+///
+/// Frames are shared, so closures see bindings added to the frames they
+/// captured after they were created.
+#[derive(Clone)]
+struct Env<'a, M: NodeMeta> {
+    frames: Vec<Frame<'a, M>>,
+}
+
+/// Evaluates a lowered module, returning the struct of its fields
+pub fn eval_module<'a, M: NodeMeta>(
+    module: &Node<Expr<'a, M>, M>,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    match Env::prelude().eval(module) {
+        Ok(value) => Ok(value),
+        Err(Exit::Error(error)) => Err(error),
+        Err(Exit::Return(_)) => unreachable!("lowering rejects returns outside of functions"),
+    }
+}
+
+/// Calls a function, type or variant with positional arguments
+pub fn call<'a, M: NodeMeta>(
+    func: &Value<'a, M>,
+    args: Vec<Value<'a, M>>,
+    meta: M,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    let args = args.into_iter().map(|value| (None, value)).collect();
+    Ok(apply(func, args, &meta)?.value)
+}
+
+impl<'a, M: NodeMeta> Value<'a, M> {
+    /// Returns the empty struct `()`
+    pub fn unit() -> Self {
+        Value::Struct(StructValue {
+            ty: None,
+            fields: Vec::new(),
+        })
+    }
+
+    /// Returns the value of the field named `name`, looking up methods in the
+    /// type a struct was constructed from
+    pub fn get(&self, name: &str) -> Option<Value<'a, M>> {
+        match self {
+            Value::Struct(struct_) => struct_
+                .fields
+                .iter()
+                .rev()
+                .find(|(field, _)| &**field == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| struct_.ty.as_ref()?.get(name)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> StructValue<'a, M> {
+    fn get(&self, name: &str) -> Option<Value<'a, M>> {
+        Value::Struct(self.clone()).get(name)
+    }
+}
+
+impl<'a, M: NodeMeta> PartialEq for Value<'a, M> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Prim(a), Value::Prim(b)) => compare(a, b) == Some(Ordering::Equal),
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Struct(a), Value::Struct(b)) => a == b,
+            (Value::Enum(a), Value::Enum(b)) => Rc::ptr_eq(a, b),
+            (
+                Value::Variant { name, value },
+                Value::Variant {
+                    name: other_name,
+                    value: other_value,
+                },
+            ) => name == other_name && value == other_value,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::ListType(a), Value::ListType(b)) => a == b,
+            (Value::Func(a), Value::Func(b)) => Rc::ptr_eq(a, b),
+            (Value::Dispatch(a), Value::Dispatch(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> fmt::Debug for Closure<'a, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure").field("func", &self.func).finish()
+    }
+}
+
+impl<'a, M: NodeMeta> fmt::Display for Value<'a, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Stmt::Bind(Ident::Void, _) => Ok(None),
-            Stmt::Bind(ident, expr) => {
-                let expr = expr.eval(ctxt)?.ok_or("expr did not eval")?;
-                ctxt.insert(ident.clone(), expr);
-                Ok(None)
+            Value::Prim(Prim::String(s)) => write!(f, "{s}"),
+            Value::Prim(Prim::Char(c)) => write!(f, "{c}"),
+            Value::Prim(Prim::F32(n)) => write!(f, "{n:?}"),
+            Value::Prim(Prim::F64(n)) => write!(f, "{n:?}"),
+            Value::Prim(Prim::I8(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::I16(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::I32(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::I64(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::U8(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::U16(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::U32(n)) => write!(f, "{n}"),
+            Value::Prim(Prim::U64(n)) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Struct(struct_) => {
+                write!(f, "(")?;
+                for (i, (name, value)) in struct_.fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name} {value}")?;
+                }
+                write!(f, ")")
             }
-            Stmt::BindMut(ident, ty, expr) => {
-                let expr = expr.eval(ctxt)?.ok_or("expr did not eval")?;
-                ctxt.insert(ident.clone(), expr);
-                Ok(None)
+            Value::Enum(variants) => {
+                write!(f, "<")?;
+                for (i, (name, value)) in variants.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name} {value}")?;
+                }
+                write!(f, ">")
             }
-            Stmt::Expr(expr) => todo!(),
+            Value::Variant { name, value } => write!(f, "{name} {value}"),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::ListType(item) => write!(f, "[]{item}"),
+            Value::Func(_) | Value::Dispatch(_) => write!(f, "<function>"),
+            Value::Builtin(Builtin::Type(name)) => write!(f, "{name}"),
+            Value::Builtin(Builtin::Any) => write!(f, "_"),
+            Value::Builtin(_) => write!(f, "<builtin>"),
+        }
+    }
+}
+
+/// Returns the names of the data fields of a struct type, leaving out its
+/// functions
+fn data_fields<M: NodeMeta>(ty: &StructValue<'_, M>) -> Vec<Rc<str>> {
+    ty.fields
+        .iter()
+        .filter(|(_, value)| match value {
+            Value::Func(_) | Value::Dispatch(_) => false,
+            Value::Builtin(builtin) => matches!(builtin, Builtin::Type(_) | Builtin::Any),
+            _ => true,
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Matches arguments to the parameters named `params`, returning the index of
+/// the parameter each argument is passed to
+fn match_args<'a, M: NodeMeta>(
+    params: &[Rc<str>],
+    args: &[(Option<Rc<str>>, Value<'a, M>)],
+    meta: &M,
+) -> Result<Vec<usize>, EvalError<M>> {
+    let mut indices = Vec::new();
+    for (i, (name, _)) in args.iter().enumerate() {
+        let index = match name {
+            Some(name) => params.iter().position(|param| param == name),
+            None => (i < params.len()).then_some(i),
+        };
+        match index {
+            Some(index) => indices.push(index),
+            None => return Err(EvalError::TooManyArgs { meta: meta.clone() }),
+        }
+    }
+    Ok(indices)
+}
+
+/// Returns `true` if `value` is of the type `ty`
+///
+/// Values that are not types, such as those of unbound generic parameters,
+/// accept any value.
+fn is_instance<'a, M: NodeMeta>(value: &Value<'a, M>, ty: &Value<'a, M>) -> bool {
+    match (ty, value) {
+        (Value::Builtin(Builtin::Type(name)), Value::Prim(prim)) => prim.fits(name),
+        (Value::Builtin(Builtin::Type(name)), Value::Bool(_)) => &**name == "Bool",
+        (Value::Builtin(Builtin::Type(_)), _) => false,
+        // a literal parameter type only accepts that literal, converted to the
+        // type of the value when it fits
+        (Value::Prim(ty), Value::Prim(value)) => ty.cast(value.type_name()).as_ref() == Some(value),
+        (Value::Prim(_), _) => false,
+        (Value::Struct(ty), Value::Struct(value)) if ty.ty.is_none() => {
+            value.ty.as_deref() == Some(ty)
+        }
+        (Value::Struct(ty), _) if ty.ty.is_none() => false,
+        (Value::Enum(variants), Value::Variant { name, .. }) => {
+            variants.iter().any(|(variant, _)| variant == name)
+        }
+        (Value::Enum(_), _) => false,
+        (Value::ListType(item), Value::List(items)) => {
+            items.iter().all(|value| is_instance(value, item))
+        }
+        (Value::ListType(_), _) => false,
+        _ => true,
+    }
+}
+
+/// Converts a literal operand of an operator to the primitive type of the other
+/// operand when it fits that type, so `x - 1` subtracts a `U32` from a `U32`
+fn coerce_literals<'a, M: NodeMeta>(
+    call: &Call<'a, M>,
+    args: &mut [(Option<Rc<str>>, Value<'a, M>)],
+) {
+    let [(_, lhs), (_, rhs)] = args else {
+        return;
+    };
+    let is_literal = |i: usize| matches!(&*call.args[i].get().expr.get(), Expr::Prim(_));
+    let (Value::Prim(lhs_prim), Value::Prim(rhs_prim)) = (&*lhs, &*rhs) else {
+        return;
+    };
+    if lhs_prim.type_name() == rhs_prim.type_name() {
+        return;
+    }
+    if is_literal(1)
+        && let Some(prim) = rhs_prim.cast(lhs_prim.type_name())
+    {
+        *rhs = Value::Prim(prim);
+    } else if is_literal(0)
+        && let Some(prim) = lhs_prim.cast(rhs_prim.type_name())
+    {
+        *lhs = Value::Prim(prim);
+    }
+}
+
+/// Returns a value as an instance of `ty`, converting a primitive to the
+/// primitive type it fits, as `3` fits `U8`
+fn coerce<'a, M: NodeMeta>(
+    value: Value<'a, M>,
+    ty: &Value<'a, M>,
+    meta: &M,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    let mismatch = || EvalError::Mismatch {
+        expected: "a value of the declared type",
+        meta: meta.clone(),
+    };
+    match (ty, value) {
+        (Value::Builtin(Builtin::Type(name)), Value::Prim(prim)) => {
+            prim.cast(name).map(Value::Prim).ok_or_else(mismatch)
         }
+        (ty, value) if is_instance(&value, ty) => Ok(value),
+        _ => Err(mismatch()),
     }
 }
 
-impl Eval for Expr {
-    fn eval(&self, ctxt: &mut HashMap<Ident, Value>) -> Result<Option<Value>, &'static str> {
+/// Returns `true` if a function's parameters accept `args`, where untyped
+/// parameters accept any value
+///
+/// Values other than functions are assumed to accept any arguments.
+fn accepts<'a, M: NodeMeta>(func: &Value<'a, M>, args: &[(Option<Rc<str>>, Value<'a, M>)]) -> bool {
+    let closure = match func {
+        Value::Func(closure) => closure,
+        Value::Dispatch(overloads) => return overloads.iter().any(|func| accepts(func, args)),
+        _ => return true,
+    };
+    let params = &closure.func.params.get().params;
+    let names: Vec<_> = params
+        .iter()
+        .map(|param| param.get().ident.name.clone())
+        .collect();
+    let Ok(indices) = match_args(&names, args, &M::default()) else {
+        return false;
+    };
+    indices.iter().zip(args).all(|(index, (_, value))| {
+        let param = params[*index].get();
+        let ty = param.ty.as_ref().and_then(|ty| closure.env.eval(ty).ok());
+        ty.is_none_or(|ty| is_instance(value, &ty))
+    })
+}
+
+/// The value a call returns, along with the value each argument's parameter
+/// holds once it returns, which is written back to arguments passed mutably
+struct Applied<'a, M: NodeMeta> {
+    value: Value<'a, M>,
+    finals: Vec<Value<'a, M>>,
+}
+
+/// Calls a value with arguments, which may be named, converting each to the
+/// declared type of the parameter or field it is passed to
+fn apply<'a, M: NodeMeta>(
+    func: &Value<'a, M>,
+    args: Vec<(Option<Rc<str>>, Value<'a, M>)>,
+    meta: &M,
+) -> Result<Applied<'a, M>, EvalError<M>> {
+    let passed: Vec<_> = args.iter().map(|(_, value)| value.clone()).collect();
+    match func {
+        Value::Func(closure) => {
+            let params = &closure.func.params.get().params;
+            let names: Vec<_> = params
+                .iter()
+                .map(|param| param.get().ident.name.clone())
+                .collect();
+            let indices = match_args(&names, &args, meta)?;
+            let mut values: Vec<Option<Value<'a, M>>> = vec![None; params.len()];
+            for (index, (_, value)) in indices.iter().zip(args) {
+                values[*index] = Some(value);
+            }
+            for (value, param) in values.iter_mut().zip(params) {
+                if value.is_none() {
+                    let param = param.get();
+                    let Some(default) = &param.default else {
+                        return Err(EvalError::MissingArg {
+                            name: param.ident.name.clone(),
+                            meta: meta.clone(),
+                        });
+                    };
+                    *value = Some(closure.env.eval(default).map_err(Exit::into_error)?);
+                }
+                if let Some(ty) = &param.get().ty
+                    && let Ok(ty) = closure.env.eval(ty)
+                    && let Some(arg) = value.take()
+                {
+                    *value = Some(coerce(arg, &ty, meta)?);
+                }
+            }
+            let slots = names
+                .into_iter()
+                .zip(values)
+                .map(|(name, value)| Slot::new(name, value))
+                .collect();
+            let frame: Frame<'a, M> = Rc::new(RefCell::new(slots));
+            let mut env = closure.env.clone();
+            env.frames.push(frame.clone());
+            let value = match env.eval(&closure.func.body) {
+                Ok(value) | Err(Exit::Return(value)) => value,
+                Err(Exit::Error(error)) => return Err(error),
+            };
+            let frame = frame.borrow();
+            let finals = indices
+                .iter()
+                .map(|index| frame[*index].value.clone().unwrap_or_else(Value::unit))
+                .collect();
+            Ok(Applied { value, finals })
+        }
+        Value::Struct(ty) if ty.ty.is_none() => {
+            let names = data_fields(ty);
+            let indices = match_args(&names, &args, meta)?;
+            let mut values: Vec<Option<Value<'a, M>>> = vec![None; names.len()];
+            for (index, (_, value)) in indices.iter().zip(args) {
+                values[*index] = Some(value);
+            }
+            let mut fields = Vec::new();
+            for (name, value) in names.into_iter().zip(values) {
+                let Some(value) = value else {
+                    return Err(EvalError::MissingArg {
+                        name,
+                        meta: meta.clone(),
+                    });
+                };
+                let value = match ty.get(&name) {
+                    Some(field_ty) => coerce(value, &field_ty, meta)?,
+                    None => value,
+                };
+                fields.push((name, value));
+            }
+            let ty = Some(Rc::new(ty.clone()));
+            Ok(Applied {
+                value: Value::Struct(StructValue { ty, fields }),
+                finals: passed,
+            })
+        }
+        Value::Variant { name, value } => {
+            let Applied { value, finals } = apply(value, args, meta)?;
+            let variant = Value::Variant {
+                name: name.clone(),
+                value: Box::new(value),
+            };
+            Ok(Applied {
+                value: variant,
+                finals,
+            })
+        }
+        Value::Dispatch(overloads) => {
+            let func =
+                overloads
+                    .iter()
+                    .find(|func| accepts(func, &args))
+                    .ok_or(EvalError::Mismatch {
+                        expected: "arguments accepted by an overload",
+                        meta: meta.clone(),
+                    })?;
+            apply(func, args, meta)
+        }
+        Value::Builtin(builtin) => Ok(Applied {
+            value: call_builtin(builtin, &passed, meta)?,
+            finals: passed,
+        }),
+        _ => Err(EvalError::NotCallable { meta: meta.clone() }),
+    }
+}
+
+/// Calls a function provided by the runtime
+fn call_builtin<'a, M: NodeMeta>(
+    builtin: &Builtin,
+    args: &[Value<'a, M>],
+    meta: &M,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    match (builtin, args) {
+        (Builtin::Type(_) | Builtin::Any, [value]) => Ok(value.clone()),
+        (Builtin::Print, args) => {
+            for arg in args {
+                print!("{arg}");
+            }
+            Ok(Value::unit())
+        }
+        (Builtin::Println, args) => {
+            for arg in args {
+                print!("{arg}");
+            }
+            println!();
+            Ok(Value::unit())
+        }
+        (Builtin::Sqrt, [Value::Prim(Prim::F32(n))]) => Ok(Value::Prim(Prim::F32(n.sqrt()))),
+        (Builtin::Sqrt, [Value::Prim(Prim::F64(n))]) => Ok(Value::Prim(Prim::F64(n.sqrt()))),
+        (Builtin::Sqrt, [_]) => Err(EvalError::Mismatch {
+            expected: "a float",
+            meta: meta.clone(),
+        }),
+        (Builtin::Len, [Value::List(items)]) => Ok(Value::Prim(Prim::I32(items.len() as i32))),
+        (Builtin::Len, [Value::Prim(Prim::String(s))]) => {
+            Ok(Value::Prim(Prim::I32(s.chars().count() as i32)))
+        }
+        (Builtin::Len, [_]) => Err(EvalError::Mismatch {
+            expected: "a list or a string",
+            meta: meta.clone(),
+        }),
+        (Builtin::Get, [Value::List(items), Value::Prim(Prim::I32(index))]) => {
+            usize::try_from(*index)
+                .ok()
+                .and_then(|i| items.get(i))
+                .cloned()
+                .ok_or(EvalError::OutOfBounds {
+                    index: *index,
+                    len: items.len(),
+                    meta: meta.clone(),
+                })
+        }
+        (Builtin::Get, [_, _]) => Err(EvalError::Mismatch {
+            expected: "a list and an I32 index",
+            meta: meta.clone(),
+        }),
+        (Builtin::Map | Builtin::Each, [Value::List(items), func]) => {
+            let mut results = Vec::new();
+            for item in items {
+                results.push(apply(func, vec![(None, item.clone())], meta)?.value);
+            }
+            match builtin {
+                Builtin::Map => Ok(Value::List(results)),
+                _ => Ok(Value::unit()),
+            }
+        }
+        (Builtin::Map | Builtin::Each, [_, _]) => Err(EvalError::Mismatch {
+            expected: "a list and a function",
+            meta: meta.clone(),
+        }),
+        (Builtin::Concat, [Value::List(a), Value::List(b)]) => {
+            Ok(Value::List(a.iter().chain(b).cloned().collect()))
+        }
+        (Builtin::Concat, [_, _]) => Err(EvalError::Mismatch {
+            expected: "two lists",
+            meta: meta.clone(),
+        }),
+        (Builtin::Op(op), [lhs, rhs]) => eval_binop(*op, lhs, rhs, meta.clone()),
+        _ => Err(EvalError::TooManyArgs { meta: meta.clone() }),
+    }
+}
+
+/// Why an arithmetic operator could not be applied
+enum ArithError {
+    Mismatch,
+    DivideByZero,
+    Overflow,
+}
+
+macro_rules! int_arith {
+    ($op:expr, $a:expr, $b:expr, $variant:path) => {{
+        let (a, b) = ($a, $b);
+        match $op {
+            BinopKind::Add => a.checked_add(b),
+            BinopKind::Sub => a.checked_sub(b),
+            BinopKind::Mul => a.checked_mul(b),
+            BinopKind::Div if b == 0 => return Err(ArithError::DivideByZero),
+            BinopKind::Div => a.checked_div(b),
+            BinopKind::Pow => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+            _ => return Err(ArithError::Mismatch),
+        }
+        .map($variant)
+        .ok_or(ArithError::Overflow)
+    }};
+}
+
+macro_rules! float_arith {
+    ($op:expr, $a:expr, $b:expr, $variant:path) => {{
+        let (a, b) = ($a, $b);
+        Ok($variant(match $op {
+            BinopKind::Add => a + b,
+            BinopKind::Sub => a - b,
+            BinopKind::Mul => a * b,
+            BinopKind::Div => a / b,
+            BinopKind::Pow => a.powf(b),
+            _ => return Err(ArithError::Mismatch),
+        }))
+    }};
+}
+
+/// Applies an arithmetic operator to two numbers of the same type
+///
+/// A float may also be raised to an [Prim::I32] power.
+fn arith(op: BinopKind, lhs: &Prim, rhs: &Prim) -> Result<Prim, ArithError> {
+    match (lhs, rhs) {
+        (Prim::I8(a), Prim::I8(b)) => int_arith!(op, *a, *b, Prim::I8),
+        (Prim::I16(a), Prim::I16(b)) => int_arith!(op, *a, *b, Prim::I16),
+        (Prim::I32(a), Prim::I32(b)) => int_arith!(op, *a, *b, Prim::I32),
+        (Prim::I64(a), Prim::I64(b)) => int_arith!(op, *a, *b, Prim::I64),
+        (Prim::U8(a), Prim::U8(b)) => int_arith!(op, *a, *b, Prim::U8),
+        (Prim::U16(a), Prim::U16(b)) => int_arith!(op, *a, *b, Prim::U16),
+        (Prim::U32(a), Prim::U32(b)) => int_arith!(op, *a, *b, Prim::U32),
+        (Prim::U64(a), Prim::U64(b)) => int_arith!(op, *a, *b, Prim::U64),
+        (Prim::F32(a), Prim::F32(b)) => float_arith!(op, *a, *b, Prim::F32),
+        (Prim::F64(a), Prim::F64(b)) => float_arith!(op, *a, *b, Prim::F64),
+        (Prim::F32(a), Prim::I32(b)) if op == BinopKind::Pow => Ok(Prim::F32(a.powi(*b))),
+        (Prim::F64(a), Prim::I32(b)) if op == BinopKind::Pow => Ok(Prim::F64(a.powi(*b))),
+        (Prim::String(a), Prim::String(b)) if op == BinopKind::Concat => {
+            Ok(Prim::String(a.clone() + b))
+        }
+        _ => Err(ArithError::Mismatch),
+    }
+}
+
+/// Orders two primitives of the same type
+fn compare(lhs: &Prim, rhs: &Prim) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Prim::I8(a), Prim::I8(b)) => a.partial_cmp(b),
+        (Prim::I16(a), Prim::I16(b)) => a.partial_cmp(b),
+        (Prim::I32(a), Prim::I32(b)) => a.partial_cmp(b),
+        (Prim::I64(a), Prim::I64(b)) => a.partial_cmp(b),
+        (Prim::U8(a), Prim::U8(b)) => a.partial_cmp(b),
+        (Prim::U16(a), Prim::U16(b)) => a.partial_cmp(b),
+        (Prim::U32(a), Prim::U32(b)) => a.partial_cmp(b),
+        (Prim::U64(a), Prim::U64(b)) => a.partial_cmp(b),
+        (Prim::F32(a), Prim::F32(b)) => a.partial_cmp(b),
+        (Prim::F64(a), Prim::F64(b)) => a.partial_cmp(b),
+        (Prim::String(a), Prim::String(b)) => a.partial_cmp(b),
+        (Prim::Char(a), Prim::Char(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+/// Applies an arithmetic operator to two primitives
+fn eval_arith<'a, M: NodeMeta>(
+    op: BinopKind,
+    lhs: &Prim,
+    rhs: &Prim,
+    meta: &M,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    arith(op, lhs, rhs)
+        .map(Value::Prim)
+        .map_err(|error| match error {
+            ArithError::Mismatch => EvalError::Mismatch {
+                expected: "numbers of the same type",
+                meta: meta.clone(),
+            },
+            ArithError::DivideByZero => EvalError::DivideByZero { meta: meta.clone() },
+            ArithError::Overflow => EvalError::Overflow { meta: meta.clone() },
+        })
+}
+
+/// Applies a binary operator to two values, as the builtin operator
+/// identifiers do
+fn eval_binop<'a, M: NodeMeta>(
+    op: BinopKind,
+    lhs: &Value<'a, M>,
+    rhs: &Value<'a, M>,
+    meta: M,
+) -> Result<Value<'a, M>, EvalError<M>> {
+    let ordering = |lhs: &Value<'a, M>, rhs: &Value<'a, M>| match (lhs, rhs) {
+        (Value::Prim(a), Value::Prim(b)) => compare(a, b).ok_or(EvalError::Mismatch {
+            expected: "values of the same type",
+            meta: meta.clone(),
+        }),
+        _ => Err(EvalError::Mismatch {
+            expected: "primitive values",
+            meta: meta.clone(),
+        }),
+    };
+    Ok(match op {
+        BinopKind::Eq => Value::Bool(lhs == rhs),
+        BinopKind::Ne => Value::Bool(lhs != rhs),
+        BinopKind::Lt => Value::Bool(ordering(lhs, rhs)? == Ordering::Less),
+        BinopKind::Le => Value::Bool(ordering(lhs, rhs)? != Ordering::Greater),
+        BinopKind::Gt => Value::Bool(ordering(lhs, rhs)? == Ordering::Greater),
+        BinopKind::Ge => Value::Bool(ordering(lhs, rhs)? != Ordering::Less),
+        _ => match (lhs, rhs) {
+            (Value::Prim(a), Value::Prim(b)) => eval_arith(op, a, b, &meta)?,
+            // `++` always appends a single item, even a list, so it does not
+            // depend on the shape of the item; `concat` joins two lists
+            (Value::List(a), item) if op == BinopKind::Concat => {
+                Value::List(a.iter().chain([item]).cloned().collect())
+            }
+            _ => {
+                return Err(EvalError::Mismatch {
+                    expected: "numbers of the same type",
+                    meta,
+                });
+            }
+        },
+    })
+}
+
+impl<'a, M: NodeMeta> Exit<'a, M> {
+    /// Treats a return outside of the function body being evaluated as
+    /// impossible, since defaults are evaluated outside of any function
+    fn into_error(self) -> EvalError<M> {
         match self {
-            Expr::Ident(ident) => panic!("not yet implemented"),
-            Expr::Prim(prim) => todo!(),
-            Expr::Struct(ref_cell) => todo!(),
-            Expr::Block(block) => todo!(),
-            Expr::Func(func) => todo!(),
-            Expr::Call(call) => todo!(),
-            Expr::Constructor(ident, ref_cell) => todo!(),
-            Expr::Project(project) => todo!(),
+            Exit::Error(error) => error,
+            Exit::Return(_) => unreachable!("lowering rejects returns outside of functions"),
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> Env<'a, M> {
+    /// Constructs the environment of a module, holding the builtins
+    fn prelude() -> Self {
+        let mut names: Vec<Slot<'a, M>> = [
+            "I8", "I16", "I32", "I64", "U8", "U16", "U32", "U64", "F32", "F64", "String", "Char",
+            "Bool",
+        ]
+        .into_iter()
+        .map(|name| {
+            Slot::new(
+                name.into(),
+                Some(Value::Builtin(Builtin::Type(name.into()))),
+            )
+        })
+        .collect();
+        names.push(Slot::new("true".into(), Some(Value::Bool(true))));
+        names.push(Slot::new("false".into(), Some(Value::Bool(false))));
+        names.push(Slot::new(
+            "print".into(),
+            Some(Value::Builtin(Builtin::Print)),
+        ));
+        names.push(Slot::new(
+            "println".into(),
+            Some(Value::Builtin(Builtin::Println)),
+        ));
+        names.push(Slot::new(
+            "sqrt".into(),
+            Some(Value::Builtin(Builtin::Sqrt)),
+        ));
+        names.push(Slot::new("len".into(), Some(Value::Builtin(Builtin::Len))));
+        names.push(Slot::new("get".into(), Some(Value::Builtin(Builtin::Get))));
+        names.push(Slot::new("map".into(), Some(Value::Builtin(Builtin::Map))));
+        names.push(Slot::new(
+            "each".into(),
+            Some(Value::Builtin(Builtin::Each)),
+        ));
+        names.push(Slot::new(
+            "concat".into(),
+            Some(Value::Builtin(Builtin::Concat)),
+        ));
+        for op in BinopKind::ALL {
+            names.push(Slot::new(
+                op.ident_name().into(),
+                Some(Value::Builtin(Builtin::Op(op))),
+            ));
+        }
+        Env {
+            frames: vec![Rc::new(RefCell::new(names))],
+        }
+    }
+
+    /// Finds the frame and position of the binding `ident` refers to, skipping
+    /// as many bindings of its name as it is shadowed by
+    fn find(&self, ident: &Ident) -> Option<(Frame<'a, M>, usize)> {
+        let mut nshadow = ident.nshadow;
+        for frame in self.frames.iter().rev() {
+            let names = frame.borrow();
+            for (i, slot) in names.iter().enumerate().rev() {
+                if slot.name == ident.name {
+                    if nshadow == 0 {
+                        return Some((frame.clone(), i));
+                    }
+                    nshadow -= 1;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the values of the binding `ident` refers to and of every
+    /// binding of its name it shadows, innermost first
+    fn overloads(&self, ident: &Ident) -> Vec<Value<'a, M>> {
+        (ident.nshadow..)
+            .map_while(|nshadow| {
+                self.find(&Ident {
+                    nshadow,
+                    ..ident.clone()
+                })
+            })
+            .filter_map(|(frame, i)| frame.borrow()[i].value.clone())
+            .collect()
+    }
+
+    /// Returns `true` if `ident` refers to a binding other than a builtin
+    fn binds_outside_prelude(&self, ident: &Ident) -> bool {
+        self.find(ident)
+            .is_some_and(|(frame, _)| !Rc::ptr_eq(&frame, &self.frames[0]))
+    }
+
+    /// Returns the value of a name, if it is in scope and computed
+    fn lookup(&self, ident: &Ident) -> Option<Value<'a, M>> {
+        let (frame, i) = self.find(ident)?;
+        frame.borrow()[i].value.clone()
+    }
+
+    /// Runs `f` with an empty frame pushed as the innermost scope
+    fn with_frame<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.frames.push(Rc::new(RefCell::new(Vec::new())));
+        let res = f(self);
+        self.frames.pop();
+        res
+    }
+
+    /// Adds a binding to the innermost frame, whose later values must be
+    /// instances of `ty`
+    fn bind(&self, name: &Rc<str>, value: Value<'a, M>, ty: Option<Value<'a, M>>) {
+        if let Some(frame) = self.frames.last() {
+            frame.borrow_mut().push(Slot {
+                name: name.clone(),
+                value: Some(value),
+                ty,
+            });
+        }
+    }
+
+    fn eval(&self, expr: &Node<Expr<'a, M>, M>) -> Result<Value<'a, M>, Exit<'a, M>> {
+        let meta = expr.get_meta().clone();
+        let value = match &*expr.get() {
+            Expr::Ident(ident) => self.lookup(ident).ok_or(EvalError::Unbound {
+                name: ident.name.clone(),
+                meta,
+            })?,
+            Expr::Prim(prim) => Value::Prim(prim.clone()),
+            Expr::Struct(struct_) => Value::Struct(StructValue {
+                ty: None,
+                fields: self.eval_fields(&struct_.fields)?,
+            }),
+            Expr::Enum(enum_) => Value::Enum(Rc::new(self.eval_fields(&enum_.variants)?)),
+            Expr::Block(block) => self.clone().with_frame(|env| env.eval_block(block))?,
+            Expr::Unop(unop) => {
+                let value = self.eval(&unop.expr)?;
+                match (unop.op, value) {
+                    (UnopKind::Copy, value) => value,
+                    (UnopKind::Neg, Value::Prim(prim)) => {
+                        let overflow = || EvalError::Overflow { meta: meta.clone() };
+                        let negated = match prim {
+                            Prim::F32(n) => Prim::F32(-n),
+                            Prim::F64(n) => Prim::F64(-n),
+                            Prim::I8(n) => Prim::I8(n.checked_neg().ok_or_else(overflow)?),
+                            Prim::I16(n) => Prim::I16(n.checked_neg().ok_or_else(overflow)?),
+                            Prim::I32(n) => Prim::I32(n.checked_neg().ok_or_else(overflow)?),
+                            Prim::I64(n) => Prim::I64(n.checked_neg().ok_or_else(overflow)?),
+                            Prim::U8(_) | Prim::U16(_) | Prim::U32(_) | Prim::U64(_) => {
+                                return Err(EvalError::NegateUnsigned { meta }.into());
+                            }
+                            Prim::String(_) | Prim::Char(_) => {
+                                return Err(EvalError::Mismatch {
+                                    expected: "a number",
+                                    meta,
+                                }
+                                .into());
+                            }
+                        };
+                        Value::Prim(negated)
+                    }
+                    (UnopKind::Neg, _) => {
+                        return Err(EvalError::Mismatch {
+                            expected: "a number",
+                            meta,
+                        }
+                        .into());
+                    }
+                }
+            }
+            Expr::Func(func) => Value::Func(Rc::new(Closure {
+                func: func.clone(),
+                env: self.clone(),
+            })),
+            Expr::Dispatch(dispatch) => {
+                let mut overloads = Vec::new();
+                for overload in &dispatch.overloads {
+                    overloads.push(self.eval(overload)?);
+                }
+                Value::Dispatch(Rc::new(overloads))
+            }
+            Expr::Call(call) => self.eval_call(call, meta)?,
+            Expr::Constructor(constructor) => {
+                let ty = match self.lookup(&constructor.ty) {
+                    Some(Value::Struct(ty)) => ty,
+                    Some(_) => {
+                        return Err(EvalError::Mismatch {
+                            expected: "a struct type",
+                            meta,
+                        }
+                        .into());
+                    }
+                    None => {
+                        return Err(EvalError::Unbound {
+                            name: constructor.ty.name.clone(),
+                            meta,
+                        }
+                        .into());
+                    }
+                };
+                let args = self
+                    .eval_fields(&constructor.fields.fields)?
+                    .into_iter()
+                    .map(|(name, value)| (Some(name), value))
+                    .collect();
+                apply(&Value::Struct(ty), args, &meta)?.value
+            }
+            Expr::Project(project) => {
+                let value = self.eval(&project.expr)?;
+                self.project(&value, &project.field.name, meta)?
+            }
+            Expr::Template(template) => {
+                let mut s = String::new();
+                for part in &template.parts {
+                    match part {
+                        TemplatePart::Text(text) => s += text,
+                        TemplatePart::Expr(expr) => s += &self.eval(expr)?.to_string(),
+                    }
+                }
+                Value::Prim(Prim::String(s))
+            }
+            Expr::Generic(generic) => self.clone().with_frame(|env| {
+                for param in &generic.params {
+                    let param = param.get();
+                    let value = match &param.value {
+                        Some(value) => env.eval(value)?,
+                        None => Value::Builtin(Builtin::Any),
+                    };
+                    env.bind(&param.ident.name, value, None);
+                }
+                env.eval(&generic.expr)
+            })?,
+            Expr::Instantiate(instantiate) => self.eval(&instantiate.expr)?,
+            Expr::List(list) => {
+                let mut items = Vec::new();
+                for item in &list.items {
+                    items.push(self.eval(item)?);
+                }
+                Value::List(items)
+            }
+            Expr::ListType(list_type) => Value::ListType(Box::new(self.eval(&list_type.item)?)),
+            Expr::If(if_) => match self.eval(&if_.cond)? {
+                Value::Bool(true) => {
+                    self.eval(&if_.then)?;
+                    Value::unit()
+                }
+                Value::Bool(false) => Value::unit(),
+                _ => {
+                    return Err(EvalError::Mismatch {
+                        expected: "a Bool",
+                        meta: if_.cond.get_meta().clone(),
+                    }
+                    .into());
+                }
+            },
+        };
+        Ok(value)
+    }
+
+    /// Evaluates the fields of a struct or the variants of an enum, which are
+    /// all in scope, though not computed, before the first is evaluated
+    fn eval_fields(&self, fields: &[Node<Field<'a, M>, M>]) -> Result<Fields<'a, M>, Exit<'a, M>> {
+        let names = fields
+            .iter()
+            .map(|field| Slot::new(field.get().ident.name.clone(), None))
+            .collect();
+        let frame: Frame<'a, M> = Rc::new(RefCell::new(names));
+        let mut env = self.clone();
+        env.frames.push(frame.clone());
+        let mut values = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let field = field.get();
+            let value = env.eval(&field.value)?;
+            frame.borrow_mut()[i].value = Some(value.clone());
+            values.push((field.ident.name.clone(), value));
+        }
+        Ok(values)
+    }
+
+    /// Evaluates the statements of a block in the innermost frame, returning
+    /// the value of its last statement if it is an expression
+    fn eval_block(&mut self, block: &Block<'a, M>) -> Result<Value<'a, M>, Exit<'a, M>> {
+        let mut value = Value::unit();
+        for stmt in &block.stmts {
+            value = Value::unit();
+            match &*stmt.get() {
+                Stmt::Bind(bind) => {
+                    let bound = self.eval(&bind.value)?;
+                    let (bound, ty) = match &bind.ty {
+                        Some(ty) => {
+                            let ty = self.eval(ty)?;
+                            (coerce(bound, &ty, &bind.value.get_meta())?, Some(ty))
+                        }
+                        None => (bound, None),
+                    };
+                    self.bind(&bind.name.name, bound, ty);
+                }
+                Stmt::Write(write) => {
+                    let written = self.eval(&write.value)?;
+                    self.assign(&write.target, written)?;
+                }
+                Stmt::Update(call) => {
+                    let target = call.args[0].get().expr.clone();
+                    let operand = call.args[1].get().expr.clone();
+                    let meta = stmt.get_meta().clone();
+                    self.update(&target, &mut |value| {
+                        let operand = self.eval(&operand)?;
+                        self.apply_call(call, vec![(None, value), (None, operand)], meta.clone())
+                    })?;
+                }
+                Stmt::Return(returned) => return Err(Exit::Return(self.eval(returned)?)),
+                Stmt::Expr(expr) => value = self.eval(expr)?,
+            }
+        }
+        Ok(value)
+    }
+
+    /// Evaluates a call, writing the final values of parameters back to the
+    /// arguments passed to them mutably
+    ///
+    /// A function name that is not bound outside of the prelude is looked up
+    /// among the methods of the type of the first argument, so methods can be
+    /// called outside of their type and take precedence over builtins of the
+    /// same name, such as `len`. Data fields are never called this way.
+    ///
+    /// Operator identifiers are overloaded: they call the innermost definition
+    /// whose parameters accept the arguments, falling back to the builtin
+    /// operators on primitives. A call of a dispatch set the checker resolved
+    /// calls that overload without testing the others.
+    fn eval_call(&self, call: &Call<'a, M>, meta: M) -> Result<Value<'a, M>, Exit<'a, M>> {
+        let mut args = Vec::new();
+        for arg in &call.args {
+            let arg = arg.get();
+            let name = arg.name.as_ref().map(|name| name.name.clone());
+            args.push((name, self.eval(&arg.expr)?));
+        }
+        self.apply_call(call, args, meta)
+    }
+
+    /// Calls the function of a call with the values of its arguments, as
+    /// [Env::eval_call] does once it has evaluated them
+    fn apply_call(
+        &self,
+        call: &Call<'a, M>,
+        args: Vec<(Option<Rc<str>>, Value<'a, M>)>,
+        meta: M,
+    ) -> Result<Value<'a, M>, Exit<'a, M>> {
+        let mut args = args;
+        if call.as_binop().is_some() {
+            coerce_literals(call, &mut args);
         }
+        let method = match (&*call.func.get(), args.first()) {
+            (Expr::Ident(ident), Some((_, Value::Struct(struct_))))
+                if ident.nshadow == 0 && !self.binds_outside_prelude(ident) =>
+            {
+                struct_
+                    .ty
+                    .as_ref()
+                    .and_then(|ty| ty.get(&ident.name))
+                    .filter(|method| matches!(method, Value::Func(_) | Value::Dispatch(_)))
+            }
+            _ => None,
+        };
+        let func = match (method, &*call.func.get()) {
+            (Some(method), _) => method,
+            (None, Expr::Ident(ident)) if BinopKind::from_ident_name(&ident.name).is_some() => self
+                .overloads(ident)
+                .into_iter()
+                .find(|func| accepts(func, &args))
+                .ok_or(EvalError::Mismatch {
+                    expected: "arguments accepted by an overload",
+                    meta: meta.clone(),
+                })?,
+            (None, _) => match (self.eval(&call.func)?, call.overload.get()) {
+                (Value::Dispatch(overloads), Some(i)) => overloads[i].clone(),
+                (func, _) => func,
+            },
+        };
+        let Applied { value, finals } = apply(&func, args, &meta)?;
+        for (arg, value) in call.args.iter().zip(finals) {
+            let arg = arg.get();
+            if arg.is_mut {
+                self.assign(&arg.expr, value)?;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Replaces the value of a name or of a field of a struct held by a name
+    /// with the result of `f`, which is given its current value
+    ///
+    /// Unlike reading the place and then assigning to it, this evaluates the
+    /// place once.
+    fn update(
+        &self,
+        target: &Node<Expr<'a, M>, M>,
+        f: &mut dyn FnMut(Value<'a, M>) -> Result<Value<'a, M>, Exit<'a, M>>,
+    ) -> Result<(), Exit<'a, M>> {
+        let meta = target.get_meta().clone();
+        match &*target.get() {
+            Expr::Ident(ident) => {
+                let value = f(self.eval(target)?)?;
+                self.write(ident, value, meta)
+            }
+            Expr::Project(project) => self.update(&project.expr, &mut |value| {
+                let Value::Struct(mut struct_) = value else {
+                    return Err(EvalError::Mismatch {
+                        expected: "a struct",
+                        meta: meta.clone(),
+                    }
+                    .into());
+                };
+                let Some(field) = struct_
+                    .fields
+                    .iter_mut()
+                    .rev()
+                    .find(|(name, _)| *name == project.field.name)
+                else {
+                    return Err(EvalError::NoField {
+                        name: project.field.name.clone(),
+                        meta: meta.clone(),
+                    }
+                    .into());
+                };
+                field.1 = f(field.1.clone())?;
+                if let Some(ty) = struct_
+                    .ty
+                    .as_ref()
+                    .and_then(|ty| ty.get(&project.field.name))
+                {
+                    field.1 = coerce(field.1.clone(), &ty, &meta)?;
+                }
+                Ok(Value::Struct(struct_))
+            }),
+            _ => Err(EvalError::NotAssignable { meta }.into()),
+        }
+    }
+
+    /// Writes a value to the binding `ident` refers to, converting it to the
+    /// type the binding is annotated with
+    fn write(&self, ident: &Ident, value: Value<'a, M>, meta: M) -> Result<(), Exit<'a, M>> {
+        let (frame, i) = self.find(ident).ok_or(EvalError::Unbound {
+            name: ident.name.clone(),
+            meta: meta.clone(),
+        })?;
+        let mut frame = frame.borrow_mut();
+        let slot = &mut frame[i];
+        slot.value = Some(match &slot.ty {
+            Some(ty) => coerce(value, ty, &meta)?,
+            None => value,
+        });
+        Ok(())
+    }
+
+    /// Writes a value to a name or to a field of a struct held by a name
+    fn assign(
+        &self,
+        target: &Node<Expr<'a, M>, M>,
+        value: Value<'a, M>,
+    ) -> Result<(), Exit<'a, M>> {
+        let meta = target.get_meta().clone();
+        match &*target.get() {
+            Expr::Ident(ident) => self.write(ident, value, meta),
+            Expr::Project(project) => {
+                let Value::Struct(mut struct_) = self.eval(&project.expr)? else {
+                    return Err(EvalError::Mismatch {
+                        expected: "a struct",
+                        meta,
+                    }
+                    .into());
+                };
+                let Some(field) = struct_
+                    .fields
+                    .iter_mut()
+                    .rev()
+                    .find(|(name, _)| *name == project.field.name)
+                else {
+                    return Err(EvalError::NoField {
+                        name: project.field.name.clone(),
+                        meta,
+                    }
+                    .into());
+                };
+                field.1 = match struct_
+                    .ty
+                    .as_ref()
+                    .and_then(|ty| ty.get(&project.field.name))
+                {
+                    Some(ty) => coerce(value, &ty, &meta)?,
+                    None => value,
+                };
+                self.assign(&project.expr, Value::Struct(struct_))
+            }
+            _ => Err(EvalError::NotAssignable { meta }.into()),
+        }
+    }
+
+    /// Returns a field of a struct or a variant of an enum
+    fn project(
+        &self,
+        value: &Value<'a, M>,
+        name: &Rc<str>,
+        meta: M,
+    ) -> Result<Value<'a, M>, EvalError<M>> {
+        let field = match value {
+            Value::Enum(variants) => {
+                variants
+                    .iter()
+                    .find(|(variant, _)| variant == name)
+                    .map(|(_, ty)| Value::Variant {
+                        name: name.clone(),
+                        value: Box::new(ty.clone()),
+                    })
+            }
+            _ => value.get(name),
+        };
+        field.ok_or(EvalError::NoField {
+            name: name.clone(),
+            meta,
+        })
     }
 }
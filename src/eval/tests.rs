@@ -0,0 +1,400 @@
+use super::*;
+use crate::{
+    ir::lower::lower_module,
+    lexer::Lexer,
+    parser::{Loc, parse_module},
+};
+
+/// Evaluates a module and calls its function `name` with `args`
+fn run(
+    source: &str,
+    name: &str,
+    args: Vec<Value<'static, Loc>>,
+) -> Result<Value<'static, Loc>, EvalError<Loc>> {
+    let module =
+        parse_module(&mut Lexer::new(source)).unwrap_or_else(|e| panic!("parsing {source}: {e:?}"));
+    let ir = lower_module(&module).unwrap_or_else(|e| panic!("lowering {source}: {e:?}"));
+    let module = eval_module(&ir)?;
+    let func = module
+        .get(name)
+        .unwrap_or_else(|| panic!("{source} has no field {name}"));
+    call(&func, args, Loc::default())
+}
+
+fn i32(n: i32) -> Value<'static, Loc> {
+    Value::Prim(Prim::I32(n))
+}
+
+fn f32(n: f32) -> Value<'static, Loc> {
+    Value::Prim(Prim::F32(n))
+}
+
+#[test]
+fn test_eval_arithmetic() {
+    assert_eq!(run("f(x I32) x * 2 + 1", "f", vec![i32(3)]), Ok(i32(7)));
+    assert_eq!(run("f(x F32) x^2", "f", vec![f32(1.5)]), Ok(f32(2.25)));
+    assert_eq!(
+        run(
+            "f(x String) \"<{x ++ \"!\"}>\"",
+            "f",
+            vec![Value::Prim(Prim::String("hi".into()))]
+        ),
+        Ok(Value::Prim(Prim::String("<hi!>".into())))
+    );
+    assert_eq!(
+        run("f(x I32) x < 2", "f", vec![i32(1)]),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        run("f(x I32) x >= 2", "f", vec![i32(1)]),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(
+        run("f(x I32) x == 1", "f", vec![i32(1)]),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        run("f(x F32) x / x == x / x", "f", vec![f32(0.0)]),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(
+        run("f(x F32) x == -x", "f", vec![f32(0.0)]),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        run("V (x F32)\nmain() V(1.0).x * 2", "main", vec![]),
+        Ok(f32(2.0))
+    );
+    assert_eq!(
+        run("f(x U8) 1 + x", "f", vec![Value::Prim(Prim::U8(2))]),
+        Ok(Value::Prim(Prim::U8(3)))
+    );
+    assert!(matches!(
+        run("f(x U8) x + 300", "f", vec![Value::Prim(Prim::U8(2))]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert_eq!(Prim::F32(f32::NAN), Prim::F32(f32::NAN));
+    assert_ne!(Prim::F32(0.0), Prim::F32(-0.0));
+}
+
+#[test]
+fn test_eval_conditionals() {
+    let source = "abs(x I32) {\n\t?(x < 0) {\n\t\t=> -x\n\t}\n\tx\n}";
+    assert_eq!(run(source, "abs", vec![i32(-3)]), Ok(i32(3)));
+    assert_eq!(run(source, "abs", vec![i32(4)]), Ok(i32(4)));
+    let source = "clamp(x I32) {\n\ty I32 = x\n\t?(y > 10) { y := 10 }\n\ty\n}";
+    assert_eq!(run(source, "clamp", vec![i32(12)]), Ok(i32(10)));
+    assert_eq!(run(source, "clamp", vec![i32(5)]), Ok(i32(5)));
+}
+
+#[test]
+fn test_eval_updates() {
+    let types = "V (x I32)\nW (v V)\n";
+    let source = format!("{types}f() {{\n\tw W = W(V(1))\n\tw.v.x += 2\n\tw.v.x *= 3\n\tw.v.x\n}}");
+    assert_eq!(run(&source, "f", vec![]), Ok(i32(9)));
+    let source = format!("{types}g() V(1)\nf() {{\n\tg().x += 1\n}}");
+    assert!(matches!(
+        run(&source, "f", vec![]),
+        Err(EvalError::NotAssignable { .. })
+    ));
+}
+
+#[test]
+fn test_eval_methods() {
+    let vector = "Vector3 (
+    x F32
+    y F32
+    z F32
+
+    Vector3:len_sq() x^2 + y^2 + z^2
+    Vector3:len() len_sq():sqrt
+
+    Vector3:unit() {
+        len = len()
+        ?(len > 0.0) {
+            => Vector3(x / len, y / len, z / len)
+        }
+        Vector3(x, y, z)
+    }
+
+    *Vector3:normalize() {
+        len = len()
+        ?(len > 0.0) {
+            x /= len
+            y /= len
+            z /= len
+        }
+    }
+)
+";
+    let source = format!("{vector}main(x F32) Vector3(x, 0.0, 0.0):unit().x");
+    assert_eq!(run(&source, "main", vec![f32(2.0)]), Ok(f32(1.0)));
+    assert_eq!(run(&source, "main", vec![f32(0.0)]), Ok(f32(0.0)));
+    let source =
+        format!("{vector}main(x F32) {{ v Vector3 = Vector3(0.0, x, 0.0), *v:normalize(), v.y }}");
+    assert_eq!(run(&source, "main", vec![f32(3.0)]), Ok(f32(1.0)));
+    let source = "V (\n\tx I32\n\tV:len() 5\n)\nmain() [V(1):len(), len(V(1))]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(5), i32(5)]))
+    );
+    let source = "V (x F32)\nx(v V) 7\nmain() x(V(1.0))";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(7)));
+}
+
+#[test]
+fn test_eval_args() {
+    let source = "f(a I32, b I32 = 2) a - b\nmain() f(b=1, a=5) * f(3)";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(4)));
+    assert_eq!(
+        run("f(a I32) a\nmain() f()", "main", vec![]),
+        Err(EvalError::MissingArg {
+            name: "a".into(),
+            meta: Loc {
+                start: 18,
+                end: 21,
+                ..Loc::default()
+            },
+        })
+    );
+}
+
+#[test]
+fn test_eval_errors() {
+    assert!(matches!(
+        run("f(x I32) x / 0", "f", vec![i32(1)]),
+        Err(EvalError::DivideByZero { .. })
+    ));
+    assert!(matches!(
+        run("f(x I32) y", "f", vec![i32(1)]),
+        Err(EvalError::Unbound { name, .. }) if &*name == "y"
+    ));
+    assert!(matches!(
+        run("f(x I32) ?(x) 1", "f", vec![i32(1)]),
+        Err(EvalError::Mismatch {
+            expected: "a Bool",
+            ..
+        })
+    ));
+    assert!(matches!(
+        run("f(x U8) -x", "f", vec![Value::Prim(Prim::U8(1))]),
+        Err(EvalError::NegateUnsigned { .. })
+    ));
+    assert_eq!(
+        run("f(x I8) -x", "f", vec![Value::Prim(Prim::I8(1))]),
+        Ok(Value::Prim(Prim::I8(-1)))
+    );
+    assert!(matches!(
+        run("f(x I8) -x", "f", vec![Value::Prim(Prim::I8(i8::MIN))]),
+        Err(EvalError::Overflow { .. })
+    ));
+}
+
+#[test]
+fn test_eval_annotations() {
+    assert!(matches!(
+        run("f(x U32) x\nmain() f(-1)", "main", vec![]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert!(matches!(
+        run("f(x String) x\nmain() f(3)", "main", vec![]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert_eq!(
+        run("f(x U8) x\nmain() f(3)", "main", vec![]),
+        Ok(Value::Prim(Prim::U8(3)))
+    );
+    assert_eq!(
+        run("f(x F64 = 1) x", "f", vec![]),
+        Ok(Value::Prim(Prim::F64(1.0)))
+    );
+    assert_eq!(
+        run("f() {\n\tx U8 = 3\n\tx\n}", "f", vec![]),
+        Ok(Value::Prim(Prim::U8(3)))
+    );
+    assert!(matches!(
+        run("f() {\n\tx U8 = 300\n\tx\n}", "f", vec![]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert!(matches!(
+        run("f() {\n\tx U8 = 3\n\tx := \"s\"\n\tx\n}", "f", vec![]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert_eq!(
+        run("f() {\n\tx U8 = 3\n\tx += 4\n\tx\n}", "f", vec![]),
+        Ok(Value::Prim(Prim::U8(7)))
+    );
+    assert_eq!(run("V (x F32)\nf() V(1).x", "f", vec![]), Ok(f32(1.0)));
+    assert!(matches!(
+        run("V (x F32)\nf() V(\"s\")", "f", vec![]),
+        Err(EvalError::Mismatch { .. })
+    ));
+    assert!(matches!(
+        run(
+            "V (x U8)\nf() {\n\tv V = V(1)\n\tv.x := 300\n\tv\n}",
+            "f",
+            vec![]
+        ),
+        Err(EvalError::Mismatch { .. })
+    ));
+}
+
+#[test]
+fn test_eval_lists() {
+    let list = |items: Vec<i32>| Value::List(items.into_iter().map(i32).collect());
+    let source =
+        "f(x I32) {\n\txs []I32 = []\n\txs ++= x\n\txs := xs:concat([x + 1, x + 2])\n\txs\n}";
+    assert_eq!(run(source, "f", vec![i32(1)]), Ok(list(vec![1, 2, 3])));
+    let source = "f() {\n\txs [][]I32 = [[1]]\n\txs ++= [2, 3]\n\txs\n}";
+    assert_eq!(
+        run(source, "f", vec![]),
+        Ok(Value::List(vec![list(vec![1]), list(vec![2, 3])]))
+    );
+    let source = "double(x I32) x * 2\nf(x I32) [x, x + 1]:map(double)";
+    assert_eq!(run(source, "f", vec![i32(3)]), Ok(list(vec![6, 8])));
+    let source = "f(i I32) [4, 5, 6]:get(i) + [1, 2]:len()";
+    assert_eq!(run(source, "f", vec![i32(2)]), Ok(i32(8)));
+    assert!(matches!(
+        run(source, "f", vec![i32(3)]),
+        Err(EvalError::OutOfBounds {
+            index: 3,
+            len: 3,
+            ..
+        })
+    ));
+    assert_eq!(run("f() \"hello\":len()", "f", vec![]), Ok(i32(5)));
+}
+
+#[test]
+fn test_eval_lambdas() {
+    let source = "f(n I32) {\n\tk = n * 10\n\t[1, 2]:map((x) x + k)\n}";
+    assert_eq!(
+        run(source, "f", vec![i32(1)]),
+        Ok(Value::List(vec![i32(11), i32(12)]))
+    );
+    let source = "make(n I32) (x) x + n\nmain() {\n\tadd = (a, b) a + b\n\tadd(make(2)(3), 4)\n}";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(9)));
+}
+
+#[test]
+fn test_eval_operator_overloads() {
+    let source = "V (x F32, y F32)
+
+(+)(lhs V, rhs V) V(lhs.x + rhs.x, lhs.y + rhs.y)
+
+main(a F32) {
+    v = V(a, 1.0) + V(2.0, 3.0)
+    v.x * 10.0 + v.y
+}";
+    assert_eq!(run(source, "main", vec![f32(1.0)]), Ok(f32(34.0)));
+    let source = "V (\n\tn I32\n\tV:(==)(rhs V) n - rhs.n == 0\n)\nmain() [V(1) == V(1), V(1) == V(2), (+)(1, 2) == 3]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Bool(true)
+        ]))
+    );
+    let source = "V (x I32)\nW (x I32)
+(+)(a V, b V) V(a.x + b.x)
+(+)(a W, b W) W(a.x + b.x)
+main() [(V(1) + V(2)).x, (W(3) + W(4)).x, 5 + 6]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(3), i32(7), i32(11)]))
+    );
+}
+
+#[test]
+fn test_eval_generics() {
+    let source = "D<T> (x T)\nid<T>(x T) x\nmain() [D<I32>(1).x, D(2).x, id(3)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(1), i32(2), i32(3)]))
+    );
+}
+
+#[test]
+fn test_eval_dispatch() {
+    let source = "fib(0) 0\nfib(1) 1\nfib(x I32) fib(x - 1) + fib(x - 2)";
+    assert_eq!(run(source, "fib", vec![i32(10)]), Ok(i32(55)));
+    let source = "fibonacci(0) 0\nfibonacci(1) 1\nfibonacci(2) 1
+fibonacci(x U32) fibonacci(x - 1) + fibonacci(x - 2)";
+    assert_eq!(
+        run(source, "fibonacci", vec![Value::Prim(Prim::U32(10))]),
+        Ok(i32(55))
+    );
+    assert_eq!(
+        run("f(0) 0\nf(x U32) 1", "f", vec![Value::Prim(Prim::U32(0))]),
+        Ok(i32(0))
+    );
+    let source = "describe(0) \"zero\"\ndescribe(x I32) \"number\"\ndescribe(x _) \"other\"
+main() [describe(0), describe(7), describe(1.5)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![
+            Value::Prim(Prim::String("zero".into())),
+            Value::Prim(Prim::String("number".into())),
+            Value::Prim(Prim::String("other".into())),
+        ]))
+    );
+    assert!(matches!(
+        run("f(0) 1\nf(1) 2\nmain(x I32) f(x)", "main", vec![i32(3)]),
+        Err(EvalError::Mismatch { .. })
+    ));
+}
+
+#[test]
+fn test_eval_overrides() {
+    let source = "add(x I32, y I32) x + y\nadd!(2, 2) 5\nmain() [add(2, 2), add(1, 2)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(5), i32(3)]))
+    );
+    let source = "add(x I32, y I32) x + y\nadd!(x I32, y I32) x - y\nmain() add(5, 2)";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(3)));
+    let source = "f(0) 1\nf!(x I32) 2\nmain() [f(0), f(1)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(2), i32(2)]))
+    );
+    let source = "add {\n\t(2, 2) 5\n\t(x I32, y I32) x + y\n}\nmain() [add(2, 2), add(2, 3)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![i32(5), i32(5)]))
+    );
+}
+
+#[test]
+fn test_eval_refinements() {
+    assert_eq!(
+        run("x I32\nx U16\nx 42\nmain() x", "main", vec![]),
+        Ok(i32(42))
+    );
+    let source = "small(x U8) \"small\"\nsmall(x I32) \"large\"\nmain() [small(200), small(300)]";
+    assert_eq!(
+        run(source, "main", vec![]),
+        Ok(Value::List(vec![
+            Value::Prim(Prim::String("small".into())),
+            Value::Prim(Prim::String("large".into())),
+        ]))
+    );
+}
+
+#[test]
+fn test_eval_inline() {
+    let source = "std (io (double(n I32) n * 2))\n..std.io\nmain() double(4)";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(8)));
+    let source = "V (x I32, y I32)\n..V(1, 2)\nmain() x + y";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(3)));
+    let source = "..(\n\ta 2\n\tsquare(n I32) n * n\n)\nmain() square(a)";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(4)));
+}
+
+#[test]
+fn test_eval_partial_inline() {
+    let source =
+        "std (double(n I32) n * 2, triple(n I32) n * 3)\n..std.(twice double)\nmain() twice(4)";
+    assert_eq!(run(source, "main", vec![]), Ok(i32(8)));
+}
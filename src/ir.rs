@@ -1,12 +1,19 @@
-use std::{collections::HashMap, rc::Rc};
+pub mod check;
+pub mod lower;
+pub mod pretty_print;
+#[cfg(test)]
+mod tests;
 
-use crate::ast::Prim;
+use std::{cell::Cell, rc::Rc};
+
+use crate::ast::{BinopKind, Prim, UnopKind};
 use crate::node::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Ident {
     pub name: Rc<str>,
     pub is_type: bool,
+    pub nshadow: usize,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,10 +21,20 @@ pub enum Expr<'a, M: NodeMeta> {
     Ident(Ident),
     Prim(Prim),
     Struct(Struct<'a, M>),
+    Enum(Enum<'a, M>),
     Block(Block<'a, M>),
+    Unop(Unop<'a, M>),
     Func(Func<'a, M>),
+    Dispatch(Dispatch<'a, M>),
     Call(Call<'a, M>),
     Constructor(Constructor<'a, M>),
+    Project(Project<'a, M>),
+    Template(Template<'a, M>),
+    Generic(Generic<'a, M>),
+    Instantiate(Instantiate<'a, M>),
+    If(If<'a, M>),
+    List(List<'a, M>),
+    ListType(ListType<'a, M>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,9 +45,19 @@ pub struct Block<'a, M: NodeMeta> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Stmt<'a, M: NodeMeta> {
     Bind(Bind<'a, M>),
+    Write(Write<'a, M>),
+    /// Writes the result of an operator back to a mutable place, as in
+    /// `x += 1`, where the call applies the operator to the place and the
+    /// operand
+    ///
+    /// The place is evaluated once, both to read and to write it.
+    Update(Call<'a, M>),
+    /// Exits the innermost enclosing function early with a value
+    Return(Node<Expr<'a, M>, M>),
     Expr(Node<Expr<'a, M>, M>),
 }
 
+/// Binds a name, which is mutable if it has a type
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Bind<'a, M: NodeMeta> {
     pub name: Ident,
@@ -44,38 +71,212 @@ pub struct Write<'a, M: NodeMeta> {
     pub value: Node<Expr<'a, M>, M>,
 }
 
+/// A struct, whose fields have unique names and are kept in declaration order
+///
+/// Functions declared under the same name are grouped into a single
+/// [Dispatch] field.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Struct<'a, M: NodeMeta> {
-    pub fields: HashMap<Ident, Node<Expr<'a, M>, M>>,
-    pub parent: Option<NodeWeak<Expr<'a, M>, M>>,
+    pub fields: Vec<Node<Field<'a, M>, M>>,
+}
+
+/// A type whose values are each exactly one of its variants, which have unique
+/// names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum<'a, M: NodeMeta> {
+    pub variants: Vec<Node<Field<'a, M>, M>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field<'a, M: NodeMeta> {
+    pub ident: Ident,
+    pub value: Node<Expr<'a, M>, M>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unop<'a, M: NodeMeta> {
+    pub op: UnopKind,
+    pub expr: Node<Expr<'a, M>, M>,
+}
+
+/// Functions declared under the same name, which a call tries from first to
+/// last until one accepts its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dispatch<'a, M: NodeMeta> {
+    pub overloads: Vec<Node<Expr<'a, M>, M>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Call<'a, M: NodeMeta> {
     pub func: Node<Expr<'a, M>, M>,
-    pub args: Vec<Node<Expr<'a, M>, M>>,
+    pub args: Vec<Node<Arg<'a, M>, M>>,
+    /// The overload of the dispatch set being called that the checker
+    /// resolved the call to, if the argument types determine it
+    pub overload: Cell<Option<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg<'a, M: NodeMeta> {
+    pub name: Option<Ident>,
+    pub expr: Node<Expr<'a, M>, M>,
+    pub is_mut: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Func<'a, M: NodeMeta> {
     pub params: Node<Params<'a, M>, M>,
-    pub body: Node<Block<'a, M>, M>,
+    pub body: Node<Expr<'a, M>, M>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Params<'a, M: NodeMeta> {
-    pub params: HashMap<Ident, Arg<'a, M>>,
-    pub parent: Option<NodeWeak<Expr<'a, M>, M>>,
+    pub params: Vec<Node<Param<'a, M>, M>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Arg<'a, M: NodeMeta> {
-    pub ty: Node<Expr<'a, M>, M>,
+pub struct Param<'a, M: NodeMeta> {
+    pub ident: Ident,
+    /// The type of the parameter, which is inferred if omitted
+    pub ty: Option<Node<Expr<'a, M>, M>>,
     pub is_mut: bool,
+    pub default: Option<Node<Expr<'a, M>, M>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Constructor<'a, M: NodeMeta> {
-    pub ty: Node<Struct<'a, M>, M>,
+    pub ty: Ident,
     pub fields: Struct<'a, M>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project<'a, M: NodeMeta> {
+    pub expr: Node<Expr<'a, M>, M>,
+    pub field: Ident,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template<'a, M: NodeMeta> {
+    pub parts: Vec<TemplatePart<'a, M>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart<'a, M: NodeMeta> {
+    Text(String),
+    Expr(Node<Expr<'a, M>, M>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct List<'a, M: NodeMeta> {
+    pub items: Vec<Node<Expr<'a, M>, M>>,
+}
+
+/// The type of lists whose items are of type `item`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListType<'a, M: NodeMeta> {
+    pub item: Node<Expr<'a, M>, M>,
+}
+
+/// Evaluates `then` only if `cond` holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If<'a, M: NodeMeta> {
+    pub cond: Node<Expr<'a, M>, M>,
+    pub then: Node<Expr<'a, M>, M>,
+}
+
+/// A declaration that is generic over its parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generic<'a, M: NodeMeta> {
+    pub params: Vec<Node<GenericParam<'a, M>, M>>,
+    pub expr: Node<Expr<'a, M>, M>,
+}
+
+/// A generic parameter, which is fixed to `value` if it has one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericParam<'a, M: NodeMeta> {
+    pub ident: Ident,
+    pub value: Option<Node<Expr<'a, M>, M>>,
+}
+
+/// A generic declaration given its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instantiate<'a, M: NodeMeta> {
+    pub expr: Node<Expr<'a, M>, M>,
+    pub args: Vec<Node<Expr<'a, M>, M>>,
+}
+
+impl Ident {
+    /// Constructs the [Ident] of a method's implicit receiver parameter
+    pub fn self_() -> Self {
+        Ident {
+            name: "self".into(),
+            is_type: false,
+            nshadow: 0,
+        }
+    }
+
+    /// Returns `true` if this is `_`, which as a type accepts any value
+    pub fn is_void(&self) -> bool {
+        &*self.name == "_"
+    }
+
+    /// Constructs the [Ident] naming an operator, such as `(+)`
+    pub fn operator(op: BinopKind) -> Self {
+        Ident {
+            name: op.ident_name().into(),
+            is_type: false,
+            nshadow: 0,
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> Call<'a, M> {
+    /// Constructs the call of an operator, which every binary operation
+    /// lowers to
+    pub fn binop(lhs: Node<Expr<'a, M>, M>, op: BinopKind, rhs: Node<Expr<'a, M>, M>) -> Self {
+        let meta = lhs.get_meta().clone();
+        Call {
+            func: Expr::Ident(Ident::operator(op)).node(meta),
+            args: [lhs, rhs]
+                .into_iter()
+                .map(|expr| {
+                    let meta = expr.get_meta().clone();
+                    Arg {
+                        name: None,
+                        expr,
+                        is_mut: false,
+                    }
+                    .node(meta)
+                })
+                .collect(),
+            overload: Cell::new(None),
+        }
+    }
+
+    /// Returns the operator this call can be written as, if it calls an
+    /// operator identifier with two positional arguments
+    pub fn as_binop(&self) -> Option<BinopKind> {
+        match &*self.func.get() {
+            Expr::Ident(ident)
+                if ident.nshadow == 0
+                    && self.args.len() == 2
+                    && self.args.iter().all(|arg| {
+                        let arg = arg.get();
+                        arg.name.is_none() && !arg.is_mut
+                    }) =>
+            {
+                BinopKind::from_ident_name(&ident.name)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> Struct<'a, M> {
+    /// Returns the value of the field named `name`
+    pub fn get(&self, name: &str) -> Option<Node<Expr<'a, M>, M>> {
+        self.fields
+            .iter()
+            .find(|field| &*field.get().ident.name == name)
+            .map(|field| field.get().value.clone())
+    }
+}
@@ -0,0 +1,439 @@
+use std::rc::Rc;
+
+use super::*;
+use crate::subtype::TypeRelation;
+
+/// An error found while checking the IR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckError<M: NodeMeta> {
+    /// A generic declaration is given the wrong number of arguments
+    TypeArgCount {
+        name: Rc<str>,
+        expected: usize,
+        found: usize,
+        meta: M,
+    },
+    /// An overload only accepts arguments that an earlier overload of the same
+    /// name already accepts
+    UnreachableOverload { name: Rc<str>, first: M, second: M },
+    /// Two overloads of the same name accept exactly the same arguments
+    AmbiguousOverload { name: Rc<str>, first: M, second: M },
+    /// No overload of a dispatch set accepts the types of a call's arguments
+    NoOverload { name: Rc<str>, meta: M },
+}
+
+/// Checks a lowered module, resolving each call of a dispatch set whose
+/// argument types determine the overload it calls
+pub fn check_module<'a, M: NodeMeta>(module: &Node<Expr<'a, M>, M>) -> Result<(), CheckError<M>> {
+    Checker { frames: Vec::new() }.check(module)
+}
+
+/// The parameter types of an overload, where [None] accepts any value
+struct Signature<'a, M: NodeMeta> {
+    params: Vec<Option<Node<Expr<'a, M>, M>>>,
+    /// The number of parameters without defaults
+    required: usize,
+    meta: M,
+}
+
+/// What the checker knows about a name in scope
+struct Binding<'a, M: NodeMeta> {
+    name: Rc<str>,
+    /// The number of generic parameters its declaration takes, if it is one
+    arity: Option<usize>,
+    /// The type of its value, if known
+    ty: Option<Node<Expr<'a, M>, M>>,
+    /// Whether it is a struct type, which calls construct instances of
+    is_struct: bool,
+    /// The overloads of the dispatch set it is bound to, if it is one
+    overloads: Vec<Signature<'a, M>>,
+}
+
+/// How a call of a dispatch set resolves, given the types of its arguments
+enum Resolution {
+    Overload(usize),
+    /// The overload depends on the values of the arguments
+    Runtime,
+    /// No overload accepts the arguments
+    Unmatched,
+}
+
+/// The names in scope while checking, innermost last
+struct Checker<'a, M: NodeMeta> {
+    frames: Vec<Vec<Binding<'a, M>>>,
+}
+
+/// Returns the number of generic parameters a value declares, or [None] if it
+/// is not a declaration
+fn arity<M: NodeMeta>(value: &Node<Expr<'_, M>, M>) -> Option<usize> {
+    match &*value.get() {
+        Expr::Generic(generic) => Some(generic.params.len()),
+        Expr::Struct(_) | Expr::Enum(_) | Expr::Func(_) | Expr::Dispatch(_) => Some(0),
+        _ => None,
+    }
+}
+
+/// Returns the parameter types of a function, looking through any generic
+/// parameters
+fn signature<'a, M: NodeMeta>(value: &Node<Expr<'a, M>, M>) -> Option<Signature<'a, M>> {
+    match &*value.get() {
+        Expr::Generic(generic) => signature(&generic.expr),
+        Expr::Func(func) => {
+            let params = &func.params.get().params;
+            Some(Signature {
+                params: params.iter().map(|param| param.get().ty.clone()).collect(),
+                required: params
+                    .iter()
+                    .filter(|param| param.get().default.is_none())
+                    .count(),
+                meta: value.get_meta().clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Finds the overload a call whose arguments have the types `args` resolves
+/// to, where [None] is an unknown type
+///
+/// Overloads are tried in order, so the call resolves statically once an
+/// overload accepts every argument and each earlier one rejects some argument.
+fn resolve_overload<'a, M: NodeMeta>(
+    overloads: &[Signature<'a, M>],
+    args: &[Option<Node<Expr<'a, M>, M>>],
+) -> Resolution {
+    for (i, overload) in overloads.iter().enumerate() {
+        if args.len() > overload.params.len() || args.len() < overload.required {
+            continue;
+        }
+        let params = &overload.params[..args.len()];
+        if params
+            .iter()
+            .zip(args)
+            .all(|(param, arg)| arg.is_subtype_of(param))
+        {
+            return Resolution::Overload(i);
+        }
+        let rejects = params
+            .iter()
+            .zip(args)
+            .any(|(param, arg)| match (param, arg) {
+                (Some(param), Some(arg)) => !arg.is_subtype_of(param) && !param.is_subtype_of(arg),
+                _ => false,
+            });
+        if !rejects {
+            return Resolution::Runtime;
+        }
+    }
+    Resolution::Unmatched
+}
+
+/// Checks that every overload of a dispatch set accepts some arguments that
+/// the overloads before it do not
+fn check_overloads<'a, M: NodeMeta>(
+    name: &Rc<str>,
+    overloads: &[Signature<'a, M>],
+) -> Result<(), CheckError<M>> {
+    for (i, later) in overloads.iter().enumerate() {
+        for earlier in &overloads[..i] {
+            if !earlier.covers(later) {
+                continue;
+            }
+            let (name, first, second) = (name.clone(), earlier.meta.clone(), later.meta.clone());
+            let equal = earlier.required == later.required
+                && earlier.params.len() == later.params.len()
+                && later
+                    .params
+                    .iter()
+                    .zip(&earlier.params)
+                    .all(|(later, earlier)| later.is_type_equal(earlier));
+            return Err(match equal {
+                true => CheckError::AmbiguousOverload {
+                    name,
+                    first,
+                    second,
+                },
+                false => CheckError::UnreachableOverload {
+                    name,
+                    first,
+                    second,
+                },
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the function `earlier` accepts every argument the
+/// function `later` does, so `later` is never called if it is tried after
+/// `earlier`
+pub fn covers<'a, M: NodeMeta>(
+    earlier: &Node<Expr<'a, M>, M>,
+    later: &Node<Expr<'a, M>, M>,
+) -> bool {
+    match (signature(earlier), signature(later)) {
+        (Some(earlier), Some(later)) => earlier.covers(&later),
+        _ => false,
+    }
+}
+
+impl<'a, M: NodeMeta> Signature<'a, M> {
+    /// Returns `true` if this overload accepts every argument `later` does
+    fn covers(&self, later: &Self) -> bool {
+        self.required <= later.required
+            && later.params.len() <= self.params.len()
+            && later
+                .params
+                .iter()
+                .zip(&self.params)
+                .all(|(later, earlier)| later.is_subtype_of(earlier))
+    }
+}
+
+impl<'a, M: NodeMeta> Binding<'a, M> {
+    /// Constructs a binding about which nothing is known but its type
+    fn typed(name: &Rc<str>, ty: Option<Node<Expr<'a, M>, M>>) -> Self {
+        Binding {
+            name: name.clone(),
+            arity: None,
+            ty,
+            is_struct: false,
+            overloads: Vec::new(),
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> Checker<'a, M> {
+    /// Runs `f` with `bindings` pushed as the innermost scope
+    fn with_frame<T>(
+        &mut self,
+        bindings: Vec<Binding<'a, M>>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        self.frames.push(bindings);
+        let res = f(self);
+        self.frames.pop();
+        res
+    }
+
+    /// Returns what is known about a name bound to `value`
+    fn declare(&self, name: &Rc<str>, value: &Node<Expr<'a, M>, M>) -> Binding<'a, M> {
+        let declared = match &*value.get() {
+            Expr::Generic(generic) => generic.expr.clone(),
+            _ => value.clone(),
+        };
+        let overloads = match &*value.get() {
+            Expr::Dispatch(dispatch) => dispatch.overloads.iter().filter_map(signature).collect(),
+            _ => Vec::new(),
+        };
+        Binding {
+            name: name.clone(),
+            arity: arity(value),
+            ty: self.type_of(value),
+            is_struct: matches!(*declared.get(), Expr::Struct(_)),
+            overloads,
+        }
+    }
+
+    /// Returns what is known about the binding `ident` refers to, if it is in
+    /// scope
+    fn resolve(&self, ident: &Ident) -> Option<&Binding<'a, M>> {
+        let mut nshadow = ident.nshadow;
+        for binding in self
+            .frames
+            .iter()
+            .rev()
+            .flat_map(|frame| frame.iter().rev())
+        {
+            if binding.name == ident.name {
+                if nshadow == 0 {
+                    return Some(binding);
+                }
+                nshadow -= 1;
+            }
+        }
+        None
+    }
+
+    /// Returns the type of an expression, if it is known statically
+    ///
+    /// A literal's type is the literal itself, and calling a struct type
+    /// constructs a value of that type.
+    fn type_of(&self, expr: &Node<Expr<'a, M>, M>) -> Option<Node<Expr<'a, M>, M>> {
+        match &*expr.get() {
+            Expr::Prim(_) => Some(expr.clone()),
+            Expr::Ident(ident) => self.resolve(ident)?.ty.clone(),
+            Expr::Constructor(constructor) => {
+                Some(Expr::Ident(constructor.ty.clone()).node(expr.get_meta().clone()))
+            }
+            Expr::Call(call) => match &*call.func.get() {
+                Expr::Ident(ident) if self.resolve(ident)?.is_struct => Some(call.func.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Checks a call, resolving the overload it calls if it calls a dispatch
+    /// set and the types of its arguments determine the overload
+    fn check_call(&mut self, call: &Call<'a, M>, meta: &M) -> Result<(), CheckError<M>> {
+        self.check(&call.func)?;
+        for arg in &call.args {
+            self.check(&arg.get().expr)?;
+        }
+        if let Expr::Ident(ident) = &*call.func.get()
+            && let Some(binding) = self.resolve(ident)
+            && !binding.overloads.is_empty()
+            && call.args.iter().all(|arg| arg.get().name.is_none())
+        {
+            let args: Vec<_> = call
+                .args
+                .iter()
+                .map(|arg| self.type_of(&arg.get().expr))
+                .collect();
+            match resolve_overload(&binding.overloads, &args) {
+                Resolution::Overload(i) => call.overload.set(Some(i)),
+                Resolution::Runtime => {}
+                // operators fall back to the builtin operators on primitives
+                Resolution::Unmatched if call.as_binop().is_some() => {}
+                Resolution::Unmatched => {
+                    return Err(CheckError::NoOverload {
+                        name: ident.name.clone(),
+                        meta: meta.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, expr: &Node<Expr<'a, M>, M>) -> Result<(), CheckError<M>> {
+        match &*expr.get() {
+            Expr::Ident(_) | Expr::Prim(_) => Ok(()),
+            Expr::Struct(struct_) => self.check_fields(&struct_.fields),
+            Expr::Enum(enum_) => self.check_fields(&enum_.variants),
+            Expr::Block(block) => self.with_frame(Vec::new(), |checker| {
+                for stmt in &block.stmts {
+                    match &*stmt.get() {
+                        Stmt::Bind(bind) => {
+                            if let Some(ty) = &bind.ty {
+                                checker.check(ty)?;
+                            }
+                            checker.check(&bind.value)?;
+                            let mut binding = checker.declare(&bind.name.name, &bind.value);
+                            if bind.ty.is_some() {
+                                binding.ty = bind.ty.clone();
+                            }
+                            if let Some(frame) = checker.frames.last_mut() {
+                                frame.push(binding);
+                            }
+                        }
+                        Stmt::Write(write) => {
+                            checker.check(&write.target)?;
+                            checker.check(&write.value)?;
+                        }
+                        Stmt::Update(call) => checker.check_call(call, &stmt.get_meta())?,
+                        Stmt::Return(expr) | Stmt::Expr(expr) => checker.check(expr)?,
+                    }
+                }
+                Ok(())
+            }),
+            Expr::Unop(unop) => self.check(&unop.expr),
+            Expr::List(list) => {
+                for item in &list.items {
+                    self.check(item)?;
+                }
+                Ok(())
+            }
+            Expr::ListType(list_type) => self.check(&list_type.item),
+            Expr::If(if_) => {
+                self.check(&if_.cond)?;
+                self.check(&if_.then)
+            }
+            Expr::Func(func) => {
+                let mut bindings = Vec::new();
+                for param in &func.params.get().params {
+                    let param = param.get();
+                    if let Some(ty) = &param.ty {
+                        self.check(ty)?;
+                    }
+                    if let Some(default) = &param.default {
+                        self.check(default)?;
+                    }
+                    bindings.push(Binding::typed(&param.ident.name, param.ty.clone()));
+                }
+                self.with_frame(bindings, |checker| checker.check(&func.body))
+            }
+            Expr::Dispatch(dispatch) => {
+                for overload in &dispatch.overloads {
+                    self.check(overload)?;
+                }
+                Ok(())
+            }
+            Expr::Call(call) => self.check_call(call, &expr.get_meta()),
+            Expr::Constructor(constructor) => self.check_fields(&constructor.fields.fields),
+            Expr::Project(project) => self.check(&project.expr),
+            Expr::Template(template) => {
+                for part in &template.parts {
+                    if let TemplatePart::Expr(expr) = part {
+                        self.check(expr)?;
+                    }
+                }
+                Ok(())
+            }
+            Expr::Generic(generic) => {
+                let mut bindings = Vec::new();
+                for param in &generic.params {
+                    let param = param.get();
+                    if let Some(value) = &param.value {
+                        self.check(value)?;
+                    }
+                    bindings.push(Binding::typed(&param.ident.name, None));
+                }
+                self.with_frame(bindings, |checker| checker.check(&generic.expr))
+            }
+            Expr::Instantiate(instantiate) => {
+                self.check(&instantiate.expr)?;
+                for arg in &instantiate.args {
+                    self.check(arg)?;
+                }
+                if let Expr::Ident(ident) = &*instantiate.expr.get()
+                    && let Some(expected) = self.resolve(ident).and_then(|binding| binding.arity)
+                    && expected != instantiate.args.len()
+                {
+                    return Err(CheckError::TypeArgCount {
+                        name: ident.name.clone(),
+                        expected,
+                        found: instantiate.args.len(),
+                        meta: expr.get_meta().clone(),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the fields of a struct or the variants of an enum, which are in
+    /// scope while checking each other
+    fn check_fields(&mut self, fields: &[Node<Field<'a, M>, M>]) -> Result<(), CheckError<M>> {
+        let bindings = fields
+            .iter()
+            .map(|field| {
+                let field = field.get();
+                self.declare(&field.ident.name, &field.value)
+            })
+            .collect();
+        self.with_frame(bindings, |checker| {
+            for field in fields {
+                let field = field.get();
+                checker.check(&field.value)?;
+                if let Expr::Dispatch(dispatch) = &*field.value.get() {
+                    let overloads: Vec<_> =
+                        dispatch.overloads.iter().filter_map(signature).collect();
+                    check_overloads(&field.ident.name, &overloads)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,993 @@
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+use super::check::covers;
+use super::*;
+use crate::{ast, subtype::TypeRelation};
+
+/// An error encountered while lowering the AST into the IR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LowerError<M: NodeMeta> {
+    /// A method's receiver does not name anything in scope
+    UnknownReceiver { name: Rc<str>, meta: M },
+    /// A struct declares the same name as both a function and something else
+    DuplicateField { name: Rc<str>, first: M, second: M },
+    /// A struct redeclares a name with a value that is not a subtype of the
+    /// previous declaration, as in `x U16` followed by `x I32`
+    InvalidRefinement { name: Rc<str>, previous: M, new: M },
+    /// A function only accepts arguments that an earlier function of the same
+    /// name already accepts, without being marked as an override with `!`
+    ConflictingDefinition { name: Rc<str>, first: M, second: M },
+    /// A function is marked as an override with `!`, but no earlier function
+    /// of the same name is declared
+    NothingToOverride { name: Rc<str>, meta: M },
+    /// An inlined expression is not a struct or enum whose fields are known
+    UnsupportedInline { meta: M },
+    /// A partial inline selects a field the inlined struct does not have
+    UnknownInlineName { name: Rc<str>, meta: M },
+    /// A name brought into scope by inlining is declared again in the same
+    /// struct, other than as another function of that name
+    ConflictingInline { name: Rc<str>, first: M, second: M },
+    /// A projection of an enum names none of its variants
+    UnknownVariant {
+        enum_: Rc<str>,
+        variant: Rc<str>,
+        meta: M,
+    },
+    /// A return statement is outside of any function
+    ReturnOutsideFunc { meta: M },
+    /// A positional argument follows a named one
+    PositionalAfterNamed { positional: M, named: M },
+    /// Two arguments are passed to the same parameter
+    DuplicateArg { name: Rc<str>, first: M, second: M },
+    /// A named argument matches no parameter of the function it is passed to
+    UnknownArg {
+        name: Rc<str>,
+        func: Rc<str>,
+        meta: M,
+    },
+}
+
+/// A set of names visible while lowering
+enum Frame<'a, M: NodeMeta> {
+    /// The fields of a struct
+    Struct(ast::Struct<'a, M>),
+    /// Function parameters and block bindings
+    Locals(Vec<Rc<str>>),
+    /// The implicit receiver of a method body, whose data fields are reached
+    /// through `self` and whose methods are passed `self`
+    Receiver {
+        fields: Vec<Rc<str>>,
+        methods: Vec<(Rc<str>, bool)>,
+    },
+}
+
+/// What an identifier refers to
+enum Resolved {
+    /// A data field of the implicit receiver
+    Field,
+    /// A method of the implicit receiver
+    Method { is_mut: bool },
+    /// Anything else, which is left as is
+    Other,
+}
+
+/// The names in scope while lowering, innermost last
+pub struct Scope<'a, M: NodeMeta> {
+    frames: Vec<Frame<'a, M>>,
+    /// The number of functions whose bodies are being lowered
+    funcs: usize,
+}
+
+impl<M: NodeMeta> Default for Scope<'_, M> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            funcs: 0,
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> Scope<'a, M> {
+    /// Runs `f` outside of the bodies of the enclosing functions, as for the
+    /// types and defaults of parameters, which are evaluated before the body
+    fn outside_funcs<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let funcs = std::mem::take(&mut self.funcs);
+        let res = f(self);
+        self.funcs = funcs;
+        res
+    }
+
+    /// Runs `f` with `frame` pushed as the innermost scope
+    fn with_frame<T>(&mut self, frame: Frame<'a, M>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.frames.push(frame);
+        let res = f(self);
+        self.frames.pop();
+        res
+    }
+
+    /// Adds a binding to the innermost set of locals
+    fn bind(&mut self, name: &Rc<str>) {
+        if let Some(Frame::Locals(names)) = self.frames.last_mut() {
+            names.push(name.clone());
+        }
+    }
+
+    /// Determines what an identifier refers to
+    fn resolve(&self, ident: &ast::Ident) -> Resolved {
+        if ident.nshadow > 0 {
+            return Resolved::Other;
+        }
+        for frame in self.frames.iter().rev() {
+            match frame {
+                Frame::Locals(names) if names.contains(&ident.name) => return Resolved::Other,
+                Frame::Struct(struct_) if field_value(&struct_.fields, &ident.name).is_some() => {
+                    return Resolved::Other;
+                }
+                Frame::Receiver { fields, methods } => {
+                    if fields.contains(&ident.name) {
+                        return Resolved::Field;
+                    }
+                    if let Some((_, is_mut)) = methods.iter().find(|(name, _)| *name == ident.name)
+                    {
+                        return Resolved::Method { is_mut: *is_mut };
+                    }
+                }
+                _ => {}
+            }
+        }
+        Resolved::Other
+    }
+
+    /// Finds the value of the innermost struct field named `name`, unless a
+    /// local shadows it
+    ///
+    /// Fields brought into a struct by inlining are found after those it
+    /// declares directly.
+    fn lookup(&self, name: &str) -> Option<Node<ast::Expr<'a, M>, M>> {
+        for frame in self.frames.iter().rev() {
+            match frame {
+                Frame::Locals(names) if names.iter().any(|local| &**local == name) => return None,
+                Frame::Struct(struct_) => {
+                    if let Some(value) = field_value(&struct_.fields, name) {
+                        return Some(value);
+                    }
+                    for field in &struct_.fields {
+                        let (expr, selected) = match &*field.get() {
+                            ast::Field::Inline(expr) => (expr.clone(), name.into()),
+                            ast::Field::PartialInline(partial) => {
+                                let Some(selected) = partial
+                                    .names
+                                    .iter()
+                                    .find(|selected| &*selected.get().scoped().name == name)
+                                else {
+                                    continue;
+                                };
+                                (partial.expr.get().clone(), selected.get().name.name.clone())
+                            }
+                            _ => continue,
+                        };
+                        if let Some(fields) = self.inlined_fields(&expr, false)
+                            && let Some(value) = field_value(&fields, &selected)
+                        {
+                            return Some(value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Finds the value of the innermost struct field named `name` that is
+    /// declared directly rather than inlined
+    fn lookup_declared(&self, name: &str) -> Option<Node<ast::Expr<'a, M>, M>> {
+        for frame in self.frames.iter().rev() {
+            match frame {
+                Frame::Locals(names) if names.iter().any(|local| &**local == name) => return None,
+                Frame::Struct(struct_) => {
+                    if let Some(value) = field_value(&struct_.fields, name) {
+                        return Some(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Returns the fields of the struct, or the variants of the enum, that an
+    /// inlined expression refers to, if they are known
+    ///
+    /// A constructed struct only has the data fields of its type. Names are
+    /// looked up through other inlined structs only if `transitive` is set,
+    /// since looking up a name may itself require the fields of an inlined
+    /// expression.
+    fn inlined_fields(
+        &self,
+        expr: &ast::Expr<'a, M>,
+        transitive: bool,
+    ) -> Option<Vec<Node<ast::Field<'a, M>, M>>> {
+        let lookup = |name: &str| match transitive {
+            true => self.lookup(name),
+            false => self.lookup_declared(name),
+        };
+        let instance = |name: &ast::Ident| {
+            let ty = lookup(&name.name).filter(|_| name.nshadow == 0)?;
+            let ty = declared(&ty);
+            let ast::Expr::Struct(ty) = &*ty.get() else {
+                return None;
+            };
+            let fields = ty
+                .fields
+                .iter()
+                .filter(
+                    |field| matches!(&*field.get(), ast::Field::Field(_, value) if is_data(value)),
+                )
+                .cloned()
+                .collect();
+            Some(fields)
+        };
+        let value = match expr {
+            ast::Expr::Struct(struct_) => return Some(struct_.fields.clone()),
+            ast::Expr::Enum(enum_) => return Some(enum_.variants.clone()),
+            ast::Expr::Ident(ident) if ident.nshadow == 0 => lookup(&ident.name)?,
+            ast::Expr::Project(project) => {
+                let fields = self.inlined_fields(&project.expr.get(), transitive)?;
+                field_value(&fields, &project.field.name)?
+            }
+            ast::Expr::Constructor(constructor) => return instance(&constructor.name),
+            ast::Expr::Call(call) => match &*call.func.get() {
+                ast::Expr::Ident(ident) => return instance(ident),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match &*declared(&value).get() {
+            ast::Expr::Struct(_) | ast::Expr::Enum(_) => {
+                self.inlined_fields(&declared(&value).get(), transitive)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the parameter names of the function or struct named `name`, if
+    /// it is in scope
+    ///
+    /// A struct's parameters are its data fields, and a method's first
+    /// parameter is its receiver. Functions declared more than once under the
+    /// same name may have different parameters, so theirs are unknown.
+    fn params(&self, name: &str) -> Option<Vec<Rc<str>>> {
+        for frame in self.frames.iter().rev() {
+            match frame {
+                Frame::Locals(names) if names.iter().any(|local| &**local == name) => return None,
+                Frame::Struct(struct_) => {
+                    let mut found = struct_
+                        .fields
+                        .iter()
+                        .filter_map(|field| match &*field.get() {
+                            ast::Field::Field(ident, value) if &*ident.name == name => {
+                                Some(match &*declared(value).get() {
+                                    ast::Expr::Func(func) => Some(param_names(func)),
+                                    ast::Expr::Struct(struct_) => Some(data_fields(struct_)),
+                                    _ => None,
+                                })
+                            }
+                            ast::Field::Method(method) if &*method.name.name == name => {
+                                let mut names = vec![Ident::self_().name];
+                                names.extend(param_names(&method.func));
+                                Some(Some(names))
+                            }
+                            _ => None,
+                        });
+                    if let Some(params) = found.next() {
+                        return match found.next() {
+                            Some(_) => None,
+                            None => params,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Returns the innermost struct being lowered
+    fn enclosing_struct(&self) -> Option<&ast::Struct<'a, M>> {
+        self.frames.iter().rev().find_map(|frame| match frame {
+            Frame::Struct(struct_) => Some(struct_),
+            _ => None,
+        })
+    }
+}
+
+/// Returns the value of the last of `fields` named `name`, where a
+/// method's value is its function
+fn field_value<'a, M: NodeMeta>(
+    fields: &[Node<ast::Field<'a, M>, M>],
+    name: &str,
+) -> Option<Node<ast::Expr<'a, M>, M>> {
+    fields.iter().rev().find_map(|field| match &*field.get() {
+        ast::Field::Field(ident, value) if &*ident.name == name => Some(value.clone()),
+        ast::Field::Method(method) if &*method.name.name == name => {
+            Some(ast::Expr::Func(method.func.clone()).node(field.get_meta().clone()))
+        }
+        _ => None,
+    })
+}
+
+/// Returns what a declaration declares, looking through any generic
+/// parameters
+fn declared<'a, M: NodeMeta>(value: &Node<ast::Expr<'a, M>, M>) -> Node<ast::Expr<'a, M>, M> {
+    match &*value.get() {
+        ast::Expr::Generic(generic) => declared(&generic.expr),
+        _ => value.clone(),
+    }
+}
+
+/// Returns the names of a function's parameters
+fn param_names<M: NodeMeta>(func: &ast::Func<'_, M>) -> Vec<Rc<str>> {
+    func.params
+        .get()
+        .params
+        .iter()
+        .map(|param| param.get().ident.name.clone())
+        .collect()
+}
+
+/// Returns the names of a struct's fields that hold data rather than
+/// functions
+fn data_fields<M: NodeMeta>(struct_: &ast::Struct<'_, M>) -> Vec<Rc<str>> {
+    struct_
+        .fields
+        .iter()
+        .filter_map(|field| match &*field.get() {
+            ast::Field::Field(ident, value) if is_data(value) => Some(ident.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns `true` if the value of a field holds data rather than a function
+fn is_data<M: NodeMeta>(value: &Node<ast::Expr<'_, M>, M>) -> bool {
+    !matches!(
+        *declared(value).get(),
+        ast::Expr::Func(_) | ast::Expr::Match(_)
+    )
+}
+
+/// Checks that positional arguments come before named ones and that no
+/// parameter is passed twice
+///
+/// If the parameters of the function are known, each named argument must
+/// also match one of them.
+fn check_args<'a, M: NodeMeta>(
+    func: &str,
+    args: &[Node<Arg<'a, M>, M>],
+    params: Option<&[Rc<str>]>,
+) -> Result<(), LowerError<M>> {
+    let mut named: Option<M> = None;
+    let mut passed: Vec<(Rc<str>, M)> = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let meta = arg.get_meta().clone();
+        let name = match (&arg.get().name, &named) {
+            (None, Some(named)) => {
+                return Err(LowerError::PositionalAfterNamed {
+                    positional: meta,
+                    named: named.clone(),
+                });
+            }
+            (None, None) => match params.and_then(|params| params.get(i)) {
+                Some(name) => name.clone(),
+                None => continue,
+            },
+            (Some(name), _) => {
+                named.get_or_insert(meta.clone());
+                if let Some(params) = params
+                    && !params.contains(&name.name)
+                {
+                    return Err(LowerError::UnknownArg {
+                        name: name.name.clone(),
+                        func: func.into(),
+                        meta,
+                    });
+                }
+                name.name.clone()
+            }
+        };
+        if let Some((_, first)) = passed.iter().find(|(passed, _)| *passed == name) {
+            return Err(LowerError::DuplicateArg {
+                name,
+                first: first.clone(),
+                second: meta,
+            });
+        }
+        passed.push((name, meta));
+    }
+    Ok(())
+}
+
+pub trait Lower<'a, M: NodeMeta> {
+    type Ir;
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>>;
+}
+
+/// Lowers a module parsed as an implicit struct
+pub fn lower_module<'a, M: NodeMeta>(
+    module: &Node<ast::Expr<'a, M>, M>,
+) -> Result<Node<Expr<'a, M>, M>, LowerError<M>> {
+    module.lower(&mut Scope::default())
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for ast::Ident {
+    type Ir = Ident;
+
+    fn lower(&self, _: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        Ok(Ident {
+            name: self.name.clone(),
+            is_type: self.is_type,
+            nshadow: self.nshadow,
+        })
+    }
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for Node<ast::Expr<'a, M>, M> {
+    type Ir = Node<Expr<'a, M>, M>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        let meta = self.get_meta().clone();
+        let expr = match &*self.get() {
+            ast::Expr::Ident(ident) => match scope.resolve(ident) {
+                Resolved::Field => Expr::Project(Project {
+                    expr: Expr::Ident(Ident::self_()).node(meta.clone()),
+                    field: ident.lower(scope)?,
+                }),
+                _ => Expr::Ident(ident.lower(scope)?),
+            },
+            ast::Expr::Prim(prim) => Expr::Prim(prim.clone()),
+            ast::Expr::Struct(struct_) => Expr::Struct(struct_.lower(scope)?),
+            ast::Expr::Enum(enum_) => Expr::Enum(enum_.lower(scope)?),
+            ast::Expr::Block(block) => Expr::Block(block.lower(scope)?),
+            ast::Expr::Unop(unop) => Expr::Unop(Unop {
+                op: unop.op,
+                expr: unop.expr.lower(scope)?,
+            }),
+            ast::Expr::Binop(binop) => Expr::Call(Call::binop(
+                binop.lhs.lower(scope)?,
+                binop.op,
+                binop.rhs.lower(scope)?,
+            )),
+            ast::Expr::Func(func) => Expr::Func(func.lower(scope)?),
+            ast::Expr::Call(call) => {
+                let mut args = call.args.lower(scope)?;
+                if !call.method_syntax
+                    && let ast::Expr::Ident(ident) = &*call.func.get()
+                    && let Resolved::Method { is_mut } = scope.resolve(ident)
+                {
+                    let receiver = Expr::Ident(Ident::self_()).node(meta.clone());
+                    args.insert(
+                        0,
+                        Arg {
+                            name: None,
+                            expr: receiver,
+                            is_mut,
+                        }
+                        .node(meta.clone()),
+                    );
+                }
+                let func = match &*call.func.get() {
+                    ast::Expr::Instantiate(instantiate) => instantiate.expr.clone(),
+                    _ => call.func.clone(),
+                };
+                let (name, params) = match &*func.get() {
+                    ast::Expr::Ident(ident) if ident.nshadow == 0 => {
+                        (ident.name.clone(), scope.params(&ident.name))
+                    }
+                    ast::Expr::Ident(ident) => (ident.name.clone(), None),
+                    _ => ("function".into(), None),
+                };
+                check_args(&name, &args, params.as_deref())?;
+                Expr::Call(Call {
+                    func: call.func.lower(scope)?,
+                    args,
+                    overload: Cell::new(None),
+                })
+            }
+            ast::Expr::Constructor(constructor) => Expr::Constructor(Constructor {
+                ty: constructor.name.lower(scope)?,
+                fields: constructor.fields.lower(scope)?,
+            }),
+            ast::Expr::Project(project) => {
+                if let ast::Expr::Ident(ident) = &*project.expr.get()
+                    && ident.nshadow == 0
+                    && let Some(value) = scope.lookup(&ident.name)
+                    && let ast::Expr::Enum(enum_) = &*value.get()
+                    && field_value(&enum_.variants, &project.field.name).is_none()
+                {
+                    return Err(LowerError::UnknownVariant {
+                        enum_: ident.name.clone(),
+                        variant: project.field.name.clone(),
+                        meta,
+                    });
+                }
+                Expr::Project(Project {
+                    expr: project.expr.lower(scope)?,
+                    field: project.field.lower(scope)?,
+                })
+            }
+            ast::Expr::Template(template) => {
+                let mut parts = Vec::new();
+                for part in &template.parts {
+                    parts.push(match part {
+                        ast::TemplatePart::Text(text) => TemplatePart::Text(text.clone()),
+                        ast::TemplatePart::Expr(expr) => TemplatePart::Expr(expr.lower(scope)?),
+                    });
+                }
+                Expr::Template(Template { parts })
+            }
+            ast::Expr::Generic(generic) => {
+                let mut params = Vec::new();
+                for param in &generic.params {
+                    let meta = param.get_meta().clone();
+                    let param = param.get();
+                    params.push(
+                        GenericParam {
+                            ident: param.ident.lower(scope)?,
+                            value: match &param.value {
+                                Some(value) => Some(value.lower(scope)?),
+                                None => None,
+                            },
+                        }
+                        .node(meta),
+                    );
+                }
+                let names = params
+                    .iter()
+                    .map(|param| param.get().ident.name.clone())
+                    .collect();
+                let expr =
+                    scope.with_frame(Frame::Locals(names), |scope| generic.expr.lower(scope))?;
+                Expr::Generic(Generic { params, expr })
+            }
+            ast::Expr::Instantiate(instantiate) => {
+                let mut args = Vec::new();
+                for arg in &instantiate.args {
+                    args.push(arg.lower(scope)?);
+                }
+                Expr::Instantiate(Instantiate {
+                    expr: instantiate.expr.lower(scope)?,
+                    args,
+                })
+            }
+            ast::Expr::List(list) => {
+                let mut items = Vec::new();
+                for item in &list.items {
+                    items.push(item.lower(scope)?);
+                }
+                Expr::List(List { items })
+            }
+            ast::Expr::ListType(list_type) => Expr::ListType(ListType {
+                item: list_type.item.lower(scope)?,
+            }),
+            ast::Expr::If(if_) => Expr::If(If {
+                cond: if_.cond.lower(scope)?,
+                then: if_.then.lower(scope)?,
+            }),
+            ast::Expr::Match(match_) => {
+                let mut overloads = Vec::new();
+                for clause in &match_.clauses {
+                    overloads.push(clause.lower(scope)?);
+                }
+                Expr::Dispatch(Dispatch { overloads })
+            }
+        };
+        Ok(expr.node(meta))
+    }
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for ast::Struct<'a, M> {
+    type Ir = Struct<'a, M>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        Ok(Struct {
+            fields: lower_fields(&self.fields, scope)?,
+        })
+    }
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for ast::Enum<'a, M> {
+    type Ir = Enum<'a, M>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        Ok(Enum {
+            variants: lower_fields(&self.variants, scope)?,
+        })
+    }
+}
+
+/// Lowers the fields of a struct or the variants of an enum, which are in
+/// scope while lowering each other
+///
+/// Functions declared under the same name are grouped into a [Dispatch], in
+/// declaration order, except that an override marked with `!` replaces the
+/// functions declared earlier that only accept arguments it accepts, and is
+/// tried before the others. A function that is not marked must accept some
+/// arguments that the earlier ones do not.
+///
+/// Anything else declared again under the same name refines it, as in `x I32`
+/// followed by `x 42`, so each declaration must be a subtype of the previous
+/// one and the last one is the value of the field. Fields brought in by
+/// inlining cannot be refined.
+fn lower_fields<'a, M: NodeMeta>(
+    fields: &[Node<ast::Field<'a, M>, M>],
+    scope: &mut Scope<'a, M>,
+) -> Result<Vec<Node<Field<'a, M>, M>>, LowerError<M>> {
+    let frame = Frame::Struct(ast::Struct {
+        fields: fields.to_vec(),
+    });
+    scope.with_frame(frame, |scope| {
+        let mut lowered = Vec::new();
+        let mut seen = HashMap::new();
+        for field in fields {
+            let meta = field.get_meta().clone();
+            let is_override = match &*field.get() {
+                ast::Field::Field(_, value) => {
+                    matches!(&*declared(value).get(), ast::Expr::Func(func) if func.is_override)
+                }
+                ast::Field::Method(method) => method.func.is_override,
+                _ => false,
+            };
+            let is_inline = matches!(
+                *field.get(),
+                ast::Field::Inline(_) | ast::Field::PartialInline(_)
+            );
+            let new = match &*field.get() {
+                ast::Field::Field(ident, value) => vec![Field {
+                    ident: ident.lower(scope)?,
+                    value: value.lower(scope)?,
+                }],
+                ast::Field::Method(method) => vec![Field {
+                    ident: method.name.lower(scope)?,
+                    value: lower_method(method, meta.clone(), scope)?,
+                }],
+                ast::Field::Inline(expr) => lower_inline(expr, meta.clone(), scope)?,
+                ast::Field::PartialInline(partial) => {
+                    lower_partial_inline(partial, meta.clone(), scope)?
+                }
+                ast::Field::Doc(_) | ast::Field::Spacer => continue,
+            };
+            for field in new {
+                let Some((previous, was_inline)) = seen.get(&field.ident.name).cloned() else {
+                    if is_override {
+                        return Err(LowerError::NothingToOverride {
+                            name: field.ident.name,
+                            meta,
+                        });
+                    }
+                    seen.insert(field.ident.name.clone(), (meta.clone(), is_inline));
+                    lowered.push(field.node(meta.clone()));
+                    continue;
+                };
+                let existing = lowered
+                    .iter()
+                    .find(|existing: &&Node<Field<'a, M>, M>| {
+                        existing.get().ident.name == field.ident.name
+                    })
+                    .expect("declared fields are lowered");
+                let value = existing.get().value.clone();
+                match (is_overload(&value), is_overload(&field.value)) {
+                    (true, true) => {}
+                    _ if was_inline || is_inline => {
+                        return Err(LowerError::ConflictingInline {
+                            name: field.ident.name,
+                            first: previous,
+                            second: meta,
+                        });
+                    }
+                    (false, false) => {
+                        if !field.value.is_subtype_of(&value) {
+                            return Err(LowerError::InvalidRefinement {
+                                name: field.ident.name,
+                                previous,
+                                new: meta,
+                            });
+                        }
+                        seen.insert(field.ident.name.clone(), (meta.clone(), false));
+                        existing.get_mut().value = field.value;
+                        continue;
+                    }
+                    _ => {
+                        return Err(LowerError::DuplicateField {
+                            name: field.ident.name,
+                            first: previous,
+                            second: meta,
+                        });
+                    }
+                }
+                let mut overloads = overloads_of(&value);
+                let added = overloads_of(&field.value);
+                if is_override {
+                    overloads.retain(|earlier| !added.iter().any(|later| covers(later, earlier)));
+                    overloads.splice(0..0, added);
+                } else {
+                    for later in &added {
+                        if let Some(earlier) =
+                            overloads.iter().find(|earlier| covers(earlier, later))
+                        {
+                            return Err(LowerError::ConflictingDefinition {
+                                name: field.ident.name,
+                                first: earlier.get_meta().clone(),
+                                second: later.get_meta().clone(),
+                            });
+                        }
+                    }
+                    overloads.extend(added);
+                }
+                existing.get_mut().value =
+                    Expr::Dispatch(Dispatch { overloads }).node(value.get_meta().clone());
+            }
+        }
+        Ok(lowered)
+    })
+}
+
+/// Returns `true` if a lowered value can be one of several functions declared
+/// under the same name
+fn is_overload<M: NodeMeta>(value: &Node<Expr<'_, M>, M>) -> bool {
+    matches!(*value.get(), Expr::Func(_) | Expr::Dispatch(_))
+}
+
+/// Returns the functions a lowered value consists of, in the order they are
+/// tried
+fn overloads_of<'a, M: NodeMeta>(value: &Node<Expr<'a, M>, M>) -> Vec<Node<Expr<'a, M>, M>> {
+    match &*value.get() {
+        Expr::Dispatch(dispatch) => dispatch.overloads.clone(),
+        _ => vec![value.clone()],
+    }
+}
+
+/// Lowers an inlined expression into the fields it brings into scope
+///
+/// The fields of a struct literal are lowered in place. Otherwise, each field
+/// of the struct or variant of the enum the expression refers to becomes a
+/// projection of it, as `..std.io` brings `print` into scope as
+/// `std.io.print`.
+fn lower_inline<'a, M: NodeMeta>(
+    expr: &ast::Expr<'a, M>,
+    meta: M,
+    scope: &mut Scope<'a, M>,
+) -> Result<Vec<Field<'a, M>>, LowerError<M>> {
+    if let ast::Expr::Struct(struct_) = expr {
+        let fields = lower_fields(&struct_.fields, scope)?;
+        return Ok(fields.iter().map(|field| field.get().clone()).collect());
+    }
+    let Some(members) = scope.inlined_fields(expr, true) else {
+        return Err(LowerError::UnsupportedInline { meta });
+    };
+    let target = expr.clone().node(meta.clone()).lower(scope)?;
+    let mut fields = Vec::new();
+    for member in &members {
+        let ident = match &*member.get() {
+            ast::Field::Field(ident, _) => ident.clone(),
+            ast::Field::Method(method) => method.name.clone(),
+            _ => continue,
+        };
+        let value = Expr::Project(Project {
+            expr: target.clone(),
+            field: ident.lower(scope)?,
+        });
+        fields.push(Field {
+            ident: ident.lower(scope)?,
+            value: value.node(meta.clone()),
+        });
+    }
+    Ok(fields)
+}
+
+/// Lowers a partial inline into the fields it selects, each of which becomes
+/// a projection of the inlined expression named by its alias, if it has one
+fn lower_partial_inline<'a, M: NodeMeta>(
+    partial: &ast::PartialInline<'a, M>,
+    meta: M,
+    scope: &mut Scope<'a, M>,
+) -> Result<Vec<Field<'a, M>>, LowerError<M>> {
+    let Some(members) = scope.inlined_fields(&partial.expr.get(), true) else {
+        return Err(LowerError::UnsupportedInline { meta });
+    };
+    let target = partial.expr.lower(scope)?;
+    let mut fields = Vec::new();
+    for selected in &partial.names {
+        let name_meta = selected.get_meta().clone();
+        let selected = selected.get();
+        if field_value(&members, &selected.name.name).is_none() {
+            return Err(LowerError::UnknownInlineName {
+                name: selected.name.name.clone(),
+                meta: name_meta,
+            });
+        }
+        let value = Expr::Project(Project {
+            expr: target.clone(),
+            field: selected.name.lower(scope)?,
+        });
+        fields.push(Field {
+            ident: selected.scoped().lower(scope)?,
+            value: value.node(meta.clone()),
+        });
+    }
+    Ok(fields)
+}
+
+/// Lowers a method into a function whose first parameter is its receiver,
+/// named `self`
+///
+/// In the body, the receiver's data fields become projections of `self`, and
+/// calls to the receiver's methods are passed `self`.
+fn lower_method<'a, M: NodeMeta>(
+    method: &ast::Method<'a, M>,
+    meta: M,
+    scope: &mut Scope<'a, M>,
+) -> Result<Node<Expr<'a, M>, M>, LowerError<M>> {
+    let Some(receiver) = scope.lookup(&method.receiver.name) else {
+        return Err(LowerError::UnknownReceiver {
+            name: method.receiver.name.clone(),
+            meta,
+        });
+    };
+    let mut fields = Vec::new();
+    let mut methods = Vec::new();
+    if let ast::Expr::Struct(struct_) = &*declared(&receiver).get() {
+        fields = data_fields(struct_);
+        for field in &struct_.fields {
+            if let ast::Field::Method(method) = &*field.get() {
+                methods.push((method.name.name.clone(), method.is_mut));
+            }
+        }
+    }
+    if let Some(enclosing) = scope.enclosing_struct() {
+        for field in &enclosing.fields {
+            if let ast::Field::Method(sibling) = &*field.get()
+                && sibling.receiver.name == method.receiver.name
+                && !methods.iter().any(|(name, _)| *name == sibling.name.name)
+            {
+                methods.push((sibling.name.name.clone(), sibling.is_mut));
+            }
+        }
+    }
+    let receiver = Param {
+        ident: Ident::self_(),
+        ty: Some(Expr::Ident(method.receiver.lower(scope)?).node(meta.clone())),
+        is_mut: method.is_mut,
+        default: None,
+    }
+    .node(meta.clone());
+    scope.with_frame(Frame::Receiver { fields, methods }, |scope| {
+        let func = lower_func(&method.func, Some(receiver), scope)?;
+        Ok(Expr::Func(func).node(meta))
+    })
+}
+
+/// Lowers a function, prepending `receiver` to its parameters if given
+fn lower_func<'a, M: NodeMeta>(
+    func: &ast::Func<'a, M>,
+    receiver: Option<Node<Param<'a, M>, M>>,
+    scope: &mut Scope<'a, M>,
+) -> Result<Func<'a, M>, LowerError<M>> {
+    let mut params: Vec<_> = receiver.into_iter().collect();
+    for param in &func.params.get().params {
+        let meta = param.get_meta().clone();
+        let param = param.get();
+        params.push(
+            Param {
+                ident: param.ident.lower(scope)?,
+                ty: match &param.expr {
+                    Some(expr) => Some(scope.outside_funcs(|scope| expr.lower(scope))?),
+                    None => None,
+                },
+                is_mut: param.is_mut,
+                default: match &param.default {
+                    Some(default) => Some(scope.outside_funcs(|scope| default.lower(scope))?),
+                    None => None,
+                },
+            }
+            .node(meta),
+        );
+    }
+    let names = params
+        .iter()
+        .map(|param| param.get().ident.name.clone())
+        .collect();
+    scope.funcs += 1;
+    let body = scope.with_frame(Frame::Locals(names), |scope| func.body.lower(scope));
+    scope.funcs -= 1;
+    let body = body?;
+    Ok(Func {
+        params: Params { params }.node(func.params.get_meta().clone()),
+        body,
+    })
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for ast::Func<'a, M> {
+    type Ir = Func<'a, M>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        lower_func(self, None, scope)
+    }
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for Node<ast::Args<'a, M>, M> {
+    type Ir = Vec<Node<Arg<'a, M>, M>>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        let mut args = Vec::new();
+        for arg in &self.get().args {
+            let meta = arg.get_meta().clone();
+            let arg = arg.get();
+            args.push(
+                Arg {
+                    name: match &arg.name {
+                        Some(name) => Some(name.lower(scope)?),
+                        None => None,
+                    },
+                    expr: arg.expr.lower(scope)?,
+                    is_mut: arg.is_mut,
+                }
+                .node(meta),
+            );
+        }
+        Ok(args)
+    }
+}
+
+impl<'a, M: NodeMeta> Lower<'a, M> for ast::Block<'a, M> {
+    type Ir = Block<'a, M>;
+
+    fn lower(&self, scope: &mut Scope<'a, M>) -> Result<Self::Ir, LowerError<M>> {
+        scope.with_frame(Frame::Locals(Vec::new()), |scope| {
+            let mut stmts = Vec::new();
+            for stmt in &self.stmts {
+                let meta = stmt.get_meta().clone();
+                let stmt = match &*stmt.get() {
+                    ast::Stmt::Bind(bind) => {
+                        let value = bind.value.lower(scope)?;
+                        scope.bind(&bind.name.name);
+                        Stmt::Bind(Bind {
+                            name: bind.name.lower(scope)?,
+                            value,
+                            ty: None,
+                        })
+                    }
+                    ast::Stmt::BindMut(bind) => {
+                        let ty = bind.initial.lower(scope)?;
+                        let value = bind.update.lower(scope)?;
+                        scope.bind(&bind.name.name);
+                        Stmt::Bind(Bind {
+                            name: bind.name.lower(scope)?,
+                            value,
+                            ty: Some(ty),
+                        })
+                    }
+                    ast::Stmt::Write(write) => Stmt::Write(Write {
+                        target: write.target.lower(scope)?,
+                        value: write.value.lower(scope)?,
+                    }),
+                    ast::Stmt::Update(update) => Stmt::Update(Call::binop(
+                        update.target.lower(scope)?,
+                        update.op,
+                        update.value.lower(scope)?,
+                    )),
+                    ast::Stmt::Return(value) => {
+                        if scope.funcs == 0 {
+                            return Err(LowerError::ReturnOutsideFunc { meta });
+                        }
+                        Stmt::Return(value.lower(scope)?)
+                    }
+                    ast::Stmt::Expr(expr) => Stmt::Expr(expr.lower(scope)?),
+                    ast::Stmt::Spacer => continue,
+                };
+                stmts.push(stmt.node(meta));
+            }
+            Ok(Block { stmts })
+        })
+    }
+}
@@ -0,0 +1,312 @@
+use super::*;
+use crate::ast::pretty_print::{PrettyPrint, PrettyPrintContext, escape};
+
+impl PrettyPrint for Ident {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        ctxt.color("^".repeat(self.nshadow), ctxt.cs.punctuation)
+            + &match self.is_type {
+                true => ctxt.style(&*self.name, ctxt.cs.type_, true, false),
+                false => ctxt.color(&*self.name, ctxt.cs.normal),
+            }
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Expr<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        match self {
+            Expr::Ident(ident) => ident.pretty_print(ctxt),
+            Expr::Prim(prim) => prim.pretty_print(ctxt),
+            Expr::Struct(struct_) => print_fields(&struct_.fields, "(", ")", ctxt),
+            Expr::Enum(enum_) => print_fields(&enum_.variants, "<", ">", ctxt),
+            Expr::Block(block) => block.pretty_print(ctxt),
+            Expr::Unop(unop) => {
+                let expr = match binop_of(&unop.expr.get()) {
+                    Some(op) if op.precedence() < BinopKind::Pow.precedence() => {
+                        parenthesize(&unop.expr.get(), ctxt)
+                    }
+                    _ => unop.expr.get().pretty_print(ctxt),
+                };
+                unop.op.pretty_print(ctxt) + &expr
+            }
+            Expr::Func(func) => func.pretty_print(ctxt),
+            Expr::Dispatch(dispatch) => {
+                let overloads: Vec<_> = dispatch
+                    .overloads
+                    .iter()
+                    .map(|overload| overload.get().pretty_print(ctxt))
+                    .collect();
+                overloads.join(&ctxt.color(", ", ctxt.cs.punctuation))
+            }
+            Expr::Call(call) if let Some(op) = call.as_binop() => {
+                let (lhs, rhs) = (&call.args[0].get().expr, &call.args[1].get().expr);
+                let prec = op.precedence();
+                let lhs = match binop_of(&lhs.get()) {
+                    Some(lhs_op)
+                        if lhs_op.precedence() < prec
+                            || (lhs_op.precedence() == prec && op.is_right_assoc()) =>
+                    {
+                        parenthesize(&lhs.get(), ctxt)
+                    }
+                    _ if op == BinopKind::Pow && matches!(*lhs.get(), Expr::Unop(_)) => {
+                        parenthesize(&lhs.get(), ctxt)
+                    }
+                    _ => lhs.get().pretty_print(ctxt),
+                };
+                let rhs = match binop_of(&rhs.get()) {
+                    Some(rhs_op)
+                        if rhs_op.precedence() < prec
+                            || (rhs_op.precedence() == prec && !op.is_right_assoc()) =>
+                    {
+                        parenthesize(&rhs.get(), ctxt)
+                    }
+                    _ => rhs.get().pretty_print(ctxt),
+                };
+                format!("{} {} {}", lhs, op.pretty_print(ctxt), rhs)
+            }
+            Expr::Call(call) => {
+                let mut s = operand(&call.func.get(), ctxt);
+                s += &ctxt.color("(", ctxt.cs.punctuation);
+                for (i, arg) in call.args.iter().enumerate() {
+                    if i > 0 {
+                        s += &ctxt.color(",", ctxt.cs.punctuation);
+                        s += " ";
+                    }
+                    let arg = arg.get();
+                    if let Some(name) = &arg.name {
+                        s += &ctxt.color(&*name.name, ctxt.cs.member);
+                        s += &ctxt.color("=", ctxt.cs.operator);
+                    }
+                    s += &ctxt.color(if arg.is_mut { "*" } else { "" }, ctxt.cs.punctuation);
+                    s += &arg.expr.get().pretty_print(ctxt);
+                }
+                s + &ctxt.color(")", ctxt.cs.punctuation)
+            }
+            Expr::Constructor(constructor) => {
+                ctxt.color("..", ctxt.cs.punctuation)
+                    + &constructor.ty.pretty_print(ctxt)
+                    + &constructor.fields.pretty_print(ctxt)
+            }
+            Expr::Project(project) => {
+                operand(&project.expr.get(), ctxt)
+                    + &ctxt.color(".", ctxt.cs.punctuation)
+                    + &ctxt.color(&*project.field.name, ctxt.cs.member)
+            }
+            Expr::Template(template) => {
+                let mut s = ctxt.color("\"", ctxt.cs.string);
+                for part in &template.parts {
+                    match part {
+                        TemplatePart::Text(text) => s += &escape(text, '"', ctxt),
+                        TemplatePart::Expr(expr) => {
+                            s += &ctxt.color("{", ctxt.cs.punctuation);
+                            s += &expr.get().pretty_print(ctxt);
+                            s += &ctxt.color("}", ctxt.cs.punctuation);
+                        }
+                    }
+                }
+                s + &ctxt.color("\"", ctxt.cs.string)
+            }
+            Expr::Generic(generic) => {
+                let mut s = ctxt.color("<", ctxt.cs.punctuation);
+                for (i, param) in generic.params.iter().enumerate() {
+                    if i > 0 {
+                        s += &ctxt.color(",", ctxt.cs.punctuation);
+                        s += " ";
+                    }
+                    let param = param.get();
+                    s += &param.ident.pretty_print(ctxt);
+                    if let Some(value) = &param.value {
+                        s += " ";
+                        s += &value.get().pretty_print(ctxt);
+                    }
+                }
+                s += &ctxt.color(">", ctxt.cs.punctuation);
+                if !matches!(*generic.expr.get(), Expr::Func(_)) {
+                    s += " ";
+                }
+                s + &generic.expr.get().pretty_print(ctxt)
+            }
+            Expr::Instantiate(instantiate) => {
+                let mut s = operand(&instantiate.expr.get(), ctxt);
+                s += &ctxt.color("<", ctxt.cs.punctuation);
+                for (i, arg) in instantiate.args.iter().enumerate() {
+                    if i > 0 {
+                        s += &ctxt.color(",", ctxt.cs.punctuation);
+                        s += " ";
+                    }
+                    s += &arg.get().pretty_print(ctxt);
+                }
+                s + &ctxt.color(">", ctxt.cs.punctuation)
+            }
+            Expr::List(list) => {
+                let mut s = ctxt.color("[", ctxt.cs.punctuation);
+                for (i, item) in list.items.iter().enumerate() {
+                    if i > 0 {
+                        s += &ctxt.color(",", ctxt.cs.punctuation);
+                        s += " ";
+                    }
+                    s += &item.get().pretty_print(ctxt);
+                }
+                s + &ctxt.color("]", ctxt.cs.punctuation)
+            }
+            Expr::ListType(list_type) => {
+                ctxt.color("[]", ctxt.cs.punctuation) + &list_type.item.get().pretty_print(ctxt)
+            }
+            Expr::If(if_) => {
+                ctxt.color("?(", ctxt.cs.operator)
+                    + &if_.cond.get().pretty_print(ctxt)
+                    + &ctxt.color(")", ctxt.cs.operator)
+                    + " "
+                    + &if_.then.get().pretty_print(ctxt)
+            }
+        }
+    }
+}
+
+/// Prints an expression wrapped in parentheses
+fn parenthesize<M: NodeMeta>(expr: &Expr<'_, M>, ctxt: &mut PrettyPrintContext) -> String {
+    ctxt.color("(", ctxt.cs.punctuation)
+        + &expr.pretty_print(ctxt)
+        + &ctxt.color(")", ctxt.cs.punctuation)
+}
+
+/// Returns the operator a call printed infix applies, if `expr` is one
+fn binop_of<M: NodeMeta>(expr: &Expr<'_, M>) -> Option<BinopKind> {
+    match expr {
+        Expr::Call(call) => call.as_binop(),
+        _ => None,
+    }
+}
+
+/// Prints an expression that is followed by a postfix operator
+fn operand<M: NodeMeta>(expr: &Expr<'_, M>, ctxt: &mut PrettyPrintContext) -> String {
+    match expr {
+        Expr::Unop(_) | Expr::Func(_) | Expr::Generic(_) | Expr::If(_) => parenthesize(expr, ctxt),
+        _ if binop_of(expr).is_some() => parenthesize(expr, ctxt),
+        _ => expr.pretty_print(ctxt),
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Struct<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        print_fields(&self.fields, "(", ")", ctxt)
+    }
+}
+
+/// Prints the fields of a struct or the variants of an enum between `open`
+/// and `close`
+///
+/// Each overload of a [Dispatch] is printed as its own field.
+fn print_fields<M: NodeMeta>(
+    fields: &[Node<Field<'_, M>, M>],
+    open: &str,
+    close: &str,
+    ctxt: &mut PrettyPrintContext,
+) -> String {
+    let mut s = ctxt.color(open, ctxt.cs.punctuation);
+    let mut first = true;
+    for field in fields {
+        let field = field.get();
+        let values = match &*field.value.get() {
+            Expr::Dispatch(dispatch) => dispatch.overloads.clone(),
+            _ => vec![field.value.clone()],
+        };
+        for value in values {
+            if !first {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+                s += " ";
+            }
+            first = false;
+            s += &ctxt.color(&*field.ident.name, ctxt.cs.member);
+            if !matches!(*value.get(), Expr::Func(_) | Expr::Generic(_)) {
+                s += " ";
+            }
+            s += &value.get().pretty_print(ctxt);
+        }
+    }
+    s + &ctxt.color(close, ctxt.cs.punctuation)
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Block<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        if self.stmts.is_empty() {
+            return ctxt.color("{}", ctxt.cs.punctuation);
+        }
+        let mut s = ctxt.color("{", ctxt.cs.punctuation);
+        for (i, stmt) in self.stmts.iter().enumerate() {
+            if i > 0 {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+            }
+            s += " ";
+            s += &stmt.get().pretty_print(ctxt);
+        }
+        s + " " + &ctxt.color("}", ctxt.cs.punctuation)
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Stmt<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        match self {
+            Stmt::Bind(bind) => {
+                let mut s = bind.name.pretty_print(ctxt) + " ";
+                if let Some(ty) = &bind.ty {
+                    s += &ty.get().pretty_print(ctxt);
+                    s += " ";
+                }
+                s + &ctxt.color("=", ctxt.cs.operator) + " " + &bind.value.get().pretty_print(ctxt)
+            }
+            Stmt::Write(write) => {
+                write.target.get().pretty_print(ctxt)
+                    + " "
+                    + &ctxt.color(":=", ctxt.cs.operator)
+                    + " "
+                    + &write.value.get().pretty_print(ctxt)
+            }
+            Stmt::Update(call) => {
+                let op = call.as_binop().expect("updates apply binary operators");
+                call.args[0].get().expr.get().pretty_print(ctxt)
+                    + " "
+                    + &op.pretty_print(ctxt)
+                    + &ctxt.color("=", ctxt.cs.operator)
+                    + " "
+                    + &call.args[1].get().expr.get().pretty_print(ctxt)
+            }
+            Stmt::Return(value) => {
+                ctxt.color("=>", ctxt.cs.operator) + " " + &value.get().pretty_print(ctxt)
+            }
+            Stmt::Expr(expr) => expr.get().pretty_print(ctxt),
+        }
+    }
+}
+
+impl<'a, M: NodeMeta> PrettyPrint for Func<'a, M> {
+    fn pretty_print(&self, ctxt: &mut PrettyPrintContext) -> String {
+        let mut s = ctxt.color("(", ctxt.cs.operator);
+        for (i, param) in self.params.get().params.iter().enumerate() {
+            if i > 0 {
+                s += &ctxt.color(",", ctxt.cs.punctuation);
+                s += " ";
+            }
+            let param = param.get();
+            if let Some(ty) = &param.ty
+                && param.ident.is_void()
+                && matches!(*ty.get(), Expr::Prim(_))
+            {
+                s += &ty.get().pretty_print(ctxt);
+                continue;
+            }
+            s += &ctxt.color(if param.is_mut { "*" } else { "" }, ctxt.cs.punctuation);
+            s += &param.ident.pretty_print(ctxt);
+            if let Some(ty) = &param.ty {
+                s += " ";
+                s += &ty.get().pretty_print(ctxt);
+            }
+            if let Some(default) = &param.default {
+                s += " ";
+                s += &ctxt.color("=", ctxt.cs.operator);
+                s += " ";
+                s += &default.get().pretty_print(ctxt);
+            }
+        }
+        s + &ctxt.color(")", ctxt.cs.operator) + " " + &self.body.get().pretty_print(ctxt)
+    }
+}
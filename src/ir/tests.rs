@@ -0,0 +1,558 @@
+use super::check::*;
+use super::lower::*;
+use super::*;
+use crate::{
+    ast::pretty_print::{PrettyPrint, PrettyPrintContext},
+    lexer::Lexer,
+    parser::{Loc, parse_module},
+};
+
+fn lower_source(source: &str) -> Result<Node<Expr<'static, Loc>, Loc>, LowerError<Loc>> {
+    let module =
+        parse_module(&mut Lexer::new(source)).unwrap_or_else(|e| panic!("parsing {source}: {e:?}"));
+    lower_module(&module)
+}
+
+fn print(expr: &Node<Expr<'_, Loc>, Loc>) -> String {
+    expr.get()
+        .pretty_print(&mut PrettyPrintContext::default().with_colors(false))
+}
+
+/// Lowers each module and compares its printed IR with the expected output
+fn assert_lowers(cases: Vec<(&str, &str)>) {
+    for (source, expected) in cases {
+        let ir = lower_source(source).unwrap_or_else(|e| panic!("lowering {source}: {e:?}"));
+        assert_eq!(print(&ir), expected, "lowering {source}");
+    }
+}
+
+#[test]
+fn test_lower_fields() {
+    assert_lowers(vec![
+        ("x 1\ny x + 2", "(x 1, y x + 2)"),
+        ("f(a I32) { b = a, b }", "(f(a I32) { b = a, b })"),
+        (
+            "f(*a I32) { b I32 = 0, b += a, a := b }",
+            "(f(*a I32) { b I32 = 0, b += a, a := b })",
+        ),
+    ]);
+}
+
+#[test]
+fn test_lower_methods() {
+    assert_lowers(vec![
+        (
+            "Vector3 (\n\tx F32\n\ty F32\n\tVector3:len_sq() x^2 + y^2\n)",
+            "(Vector3 (x F32, y F32, len_sq(self Vector3) self.x ^ 2 + self.y ^ 2))",
+        ),
+        (
+            "V (\n\tx F32\n\tV:len_sq() x^2\n\tV:len() len_sq():sqrt()\n)",
+            "(V (x F32, len_sq(self V) self.x ^ 2, len(self V) sqrt(len_sq(self))))",
+        ),
+        (
+            "V (\n\tx F32\n\t*V:scale(by F32) { x *= by }\n)",
+            "(V (x F32, scale(*self V, by F32) { self.x *= by }))",
+        ),
+        (
+            "V (\n\tx F32\n\tV:shift(x F32) x\n)",
+            "(V (x F32, shift(self V, x F32) x))",
+        ),
+        (
+            "V (\n\tx F32\n\tV:f() { x = x + 1, x }\n)",
+            "(V (x F32, f(self V) { x = self.x + 1, x }))",
+        ),
+        (
+            "V (\n\tx F32\n\t*V:reset() { x := 0 }\n\t*V:g() { reset(), h() }\n\tV:h() ^x\n)",
+            "(V (x F32, reset(*self V) { self.x := 0 }, g(*self V) { reset(*self), h(self) }, \
+             h(self V) ^x))",
+        ),
+    ]);
+}
+
+#[test]
+fn test_lower_method_outside_receiver() {
+    assert_lowers(vec![(
+        "V (x F32)\nV:get() x",
+        "(V (x F32), get(self V) self.x)",
+    )]);
+}
+
+#[test]
+fn test_lower_errors() {
+    assert!(matches!(
+        lower_source("W:get() x"),
+        Err(LowerError::UnknownReceiver { name, .. }) if &*name == "W"
+    ));
+    assert_eq!(
+        lower_source("x 1\nx 2"),
+        Err(LowerError::InvalidRefinement {
+            name: "x".into(),
+            previous: Loc {
+                start: 0,
+                end: 3,
+                ..Loc::default()
+            },
+            new: Loc {
+                start: 4,
+                end: 7,
+                ..Loc::default()
+            },
+        })
+    );
+}
+
+#[test]
+fn test_lower_named_args() {
+    assert_lowers(vec![
+        (
+            "f(a I32, b I32 = 2) a\nx f(1, b=3)",
+            "(f(a I32, b I32 = 2) a, x f(1, b=3))",
+        ),
+        (
+            "V (x F32, y F32)\nv V(y=1.0, x=2.0)",
+            "(V (x F32, y F32), v V(y=1.0, x=2.0))",
+        ),
+        ("x g(1, anything=2)", "(x g(1, anything=2))"),
+        (
+            "V (\n\tx F32\n\tV:f(a I32) a\n\tV:g() f(a=1)\n)",
+            "(V (x F32, f(self V, a I32) a, g(self V) f(self, a=1)))",
+        ),
+    ]);
+}
+
+#[test]
+fn test_lower_named_arg_errors() {
+    let loc = |start, end| Loc {
+        start,
+        end,
+        ..Loc::default()
+    };
+    assert_eq!(
+        lower_source("x g(a=1, 2)"),
+        Err(LowerError::PositionalAfterNamed {
+            positional: loc(9, 10),
+            named: loc(4, 7),
+        })
+    );
+    assert_eq!(
+        lower_source("x g(a=1, a=2)"),
+        Err(LowerError::DuplicateArg {
+            name: "a".into(),
+            first: loc(4, 7),
+            second: loc(9, 12),
+        })
+    );
+    assert_eq!(
+        lower_source("f(a I32) a\nx f(1, a=2)"),
+        Err(LowerError::DuplicateArg {
+            name: "a".into(),
+            first: loc(15, 16),
+            second: loc(18, 21),
+        })
+    );
+    assert_eq!(
+        lower_source("f(a I32) a\nx f(b=2)"),
+        Err(LowerError::UnknownArg {
+            name: "b".into(),
+            func: "f".into(),
+            meta: loc(15, 18),
+        })
+    );
+    assert!(matches!(
+        lower_source("V (x F32)\nv V(z=1.0)"),
+        Err(LowerError::UnknownArg { name, .. }) if &*name == "z"
+    ));
+}
+
+#[test]
+fn test_lower_enums() {
+    assert_lowers(vec![
+        (
+            "Bool <true (), false ()>\n..Bool\nx true",
+            "(Bool <true (), false ()>, true Bool.true, false Bool.false, x true)",
+        ),
+        (
+            "Prim <I32, String>\nx Prim.I32(1)",
+            "(Prim <I32 ^I32, String ^String>, x Prim.I32(1))",
+        ),
+    ]);
+    assert!(matches!(
+        lower_source("Bool <true (), false ()>\nx Bool.maybe"),
+        Err(LowerError::UnknownVariant { enum_, variant, .. })
+            if &*enum_ == "Bool" && &*variant == "maybe"
+    ));
+    assert!(matches!(
+        lower_source("Bool <true (), false ()>\n..Bool\ntrue 1"),
+        Err(LowerError::ConflictingInline { name, .. }) if &*name == "true"
+    ));
+    assert!(matches!(
+        lower_source("W<T> (..T)"),
+        Err(LowerError::UnsupportedInline { .. })
+    ));
+}
+
+fn check_source(source: &str) -> Result<(), CheckError<Loc>> {
+    let ir = lower_source(source).unwrap_or_else(|e| panic!("lowering {source}: {e:?}"));
+    check_module(&ir)
+}
+
+#[test]
+fn test_lower_generics() {
+    assert_lowers(vec![
+        (
+            "DynAdd<T> (lhs I32)\nany_add<T>(obj DynAdd<T>) obj.lhs",
+            "(DynAdd<T> (lhs I32), any_add<T>(obj DynAdd<T>) obj.lhs)",
+        ),
+        (
+            "Dyn<T> (\n\tx I32\n\tDyn:get() x\n)\nd Dyn<I32>(1)",
+            "(Dyn<T> (x I32, get(self Dyn) self.x), d Dyn<I32>(1))",
+        ),
+    ]);
+}
+
+#[test]
+fn test_check_type_args() {
+    let loc = |start, end| Loc {
+        start,
+        end,
+        ..Loc::default()
+    };
+    assert_eq!(
+        check_source("Pair<A, B> (a A, b B)\np Pair<I32, F32>"),
+        Ok(())
+    );
+    assert_eq!(
+        check_source("Pair<A, B> (a A, b B)\np Pair<I32>"),
+        Err(CheckError::TypeArgCount {
+            name: "Pair".into(),
+            expected: 2,
+            found: 1,
+            meta: loc(24, 33),
+        })
+    );
+    assert!(matches!(
+        check_source("V (x I32)\nv V<I32>"),
+        Err(CheckError::TypeArgCount {
+            expected: 0,
+            found: 1,
+            ..
+        })
+    ));
+    assert!(matches!(
+        check_source("id<T>(x T) x\ny { f = id<I32, I32>, f }"),
+        Err(CheckError::TypeArgCount { name, expected: 1, found: 2, .. }) if &*name == "id"
+    ));
+    assert_eq!(check_source("Wrap<T> (x T<I32>)"), Ok(()));
+}
+
+#[test]
+fn test_lower_conditionals() {
+    assert_lowers(vec![(
+        "V (\n\tx F32\n\tV:pos() {\n\t\t?(x < 0.0) { => -x }\n\t\tx\n\t}\n)",
+        "(V (x F32, pos(self V) { ?(self.x < 0.0) { => -self.x }, self.x }))",
+    )]);
+    assert!(matches!(
+        lower_source("x { => 1 }"),
+        Err(LowerError::ReturnOutsideFunc { .. })
+    ));
+    assert!(matches!(
+        lower_source("f(x I32 = { => 1 }) x"),
+        Err(LowerError::ReturnOutsideFunc { .. })
+    ));
+    assert!(matches!(
+        lower_source("f() {\n\tg = (x I32 = { => 1 }) x\n\tg()\n}"),
+        Err(LowerError::ReturnOutsideFunc { .. })
+    ));
+}
+
+#[test]
+fn test_lower_lists() {
+    assert_lowers(vec![
+        ("x [1, 2]", "(x [1, 2])"),
+        ("F (xs []I32)", "(F (xs []I32))"),
+        ("f(*xs []I32) { xs ++= 1 }", "(f(*xs []I32) { xs ++= 1 })"),
+    ]);
+}
+
+#[test]
+fn test_lower_lambdas() {
+    assert_lowers(vec![
+        ("add (a, b) a + b", "(add(a, b) a + b)"),
+        (
+            "f(n I32) [1]:map((x) x + n)",
+            "(f(n I32) map([1], (x) x + n))",
+        ),
+    ]);
+}
+
+#[test]
+fn test_lower_method_without_parens() {
+    assert_lowers(vec![(
+        "f(v V) v:len:sqrt + v:len()",
+        "(f(v V) sqrt(len(v)) + len(v))",
+    )]);
+}
+
+#[test]
+fn test_lower_operators() {
+    assert_lowers(vec![
+        ("x (1 + 2) * 3^2", "(x (1 + 2) * 3 ^ 2)"),
+        ("x (+)(1, 2) - -(3 - 4)", "(x 1 + 2 - -(3 - 4))"),
+        ("x ^(+)(1, 2)", "(x ^(+)(1, 2))"),
+    ]);
+    let ir = lower_source("x a + b").unwrap();
+    let Expr::Struct(module) = &*ir.get() else {
+        panic!("modules lower to structs");
+    };
+    let x = module.get("x").unwrap();
+    let Expr::Call(call) = &*x.get() else {
+        panic!("binary operations lower to calls");
+    };
+    assert!(matches!(&*call.func.get(), Expr::Ident(ident) if &*ident.name == "(+)"));
+}
+
+#[test]
+fn test_lower_dispatch() {
+    assert_lowers(vec![(
+        "fib(0) 0\nfib(1) 1\nfib(x I32) fib(x - 1) + fib(x - 2)",
+        "(fib(0) 0, fib(1) 1, fib(x I32) fib(x - 1) + fib(x - 2))",
+    )]);
+    let ir = lower_source("f(0) 0\nf(x I32) x").unwrap();
+    let Expr::Struct(module) = &*ir.get() else {
+        panic!("modules lower to structs");
+    };
+    let f = module.get("f").unwrap();
+    assert!(matches!(&*f.get(), Expr::Dispatch(dispatch) if dispatch.overloads.len() == 2));
+    assert!(matches!(
+        lower_source("x 1\nx(y I32) y"),
+        Err(LowerError::DuplicateField { .. })
+    ));
+}
+
+#[test]
+fn test_check_overloads() {
+    assert_eq!(check_source("f(0) 1\nf(x I32) 2\nf(x _) 3"), Ok(()));
+    assert!(matches!(
+        check_source("f {\n\t(x I32) 1\n\t(0) 2\n}"),
+        Err(CheckError::UnreachableOverload { name, .. }) if &*name == "f"
+    ));
+    assert!(matches!(
+        check_source("f {\n\t(x I32) 1\n\t(y I32) 2\n}"),
+        Err(CheckError::AmbiguousOverload { name, .. }) if &*name == "f"
+    ));
+    assert_eq!(check_source("f(0) 1\nf!(x I32) 2"), Ok(()));
+    assert_eq!(
+        check_source("add(x I32, y I32) 1\nadd!(x I32, y I32) 2"),
+        Ok(())
+    );
+    assert_eq!(check_source("f(x I32) 1\nf(x I32, y I32) 2"), Ok(()));
+    let operators =
+        "V (x I32)\nW (x I32)\n(+)(a V, b V) V(a.x + b.x)\n(+)(a W, b W) W(a.x + b.x)\n";
+    assert_eq!(check_source(&format!("{operators}main() 1 + 2")), Ok(()));
+    assert!(matches!(
+        check_source("f(0) 1\nf(x I32) 2\nmain() f(\"s\")"),
+        Err(CheckError::NoOverload { name, .. }) if &*name == "f"
+    ));
+}
+
+#[test]
+fn test_check_resolves_dispatch() {
+    let ir = lower_source("f(0) 1\nf(x I32) 2\na() f(0)\nb() f(5)\nc(n I32) f(n)").unwrap();
+    assert_eq!(check_module(&ir), Ok(()));
+    let Expr::Struct(module) = &*ir.get() else {
+        panic!("modules lower to structs");
+    };
+    let overload = |name| {
+        let func = module.get(name).unwrap();
+        let Expr::Func(func) = &*func.get() else {
+            panic!("{name} is a function");
+        };
+        let Expr::Call(call) = &*func.body.get() else {
+            panic!("{name} calls f");
+        };
+        call.overload.get()
+    };
+    assert_eq!(overload("a"), Some(0));
+    assert_eq!(overload("b"), Some(1));
+    assert_eq!(overload("c"), None);
+    let ir = lower_source("f(0) 1\nf(x U32) 2\na() f(0u32)").unwrap();
+    assert_eq!(check_module(&ir), Ok(()));
+    let Expr::Struct(module) = &*ir.get() else {
+        panic!("modules lower to structs");
+    };
+    let a = module.get("a").unwrap();
+    let Expr::Func(a) = &*a.get() else {
+        panic!("a is a function");
+    };
+    let Expr::Call(call) = &*a.body.get() else {
+        panic!("a calls f");
+    };
+    assert_eq!(call.overload.get(), Some(0));
+}
+
+#[test]
+fn test_lower_overrides() {
+    let loc = |start, end| Loc {
+        start,
+        end,
+        ..Loc::default()
+    };
+    assert_lowers(vec![
+        (
+            "add(x I32, y I32) x + y\nadd!(2, 2) 5",
+            "(add(2, 2) 5, add(x I32, y I32) x + y)",
+        ),
+        (
+            "add {\n\t(2, 2) 5\n\t(x I32, y I32) x + y\n}",
+            "(add(2, 2) 5, add(x I32, y I32) x + y)",
+        ),
+        (
+            "add(x I32, y I32) x + y\nadd!(x I32, y I32) x - y",
+            "(add(x I32, y I32) x - y)",
+        ),
+        ("f(0) 1\nf(1) 2\nf!(x I32) 3", "(f(x I32) 3)"),
+    ]);
+    assert_eq!(
+        lower_source("add(x I32, y I32) x + y\nadd(2, 2) 5"),
+        Err(LowerError::ConflictingDefinition {
+            name: "add".into(),
+            first: loc(3, 23),
+            second: loc(27, 35),
+        })
+    );
+    assert!(matches!(
+        lower_source("add(x I32, y I32) x + y\nadd(x I32, y I32) y + x"),
+        Err(LowerError::ConflictingDefinition { .. })
+    ));
+    assert!(matches!(
+        lower_source("add!(2, 2) 5"),
+        Err(LowerError::NothingToOverride { name, .. }) if &*name == "add"
+    ));
+    assert!(matches!(
+        lower_source("x 1\nx!(y I32) y"),
+        Err(LowerError::DuplicateField { .. })
+    ));
+}
+
+#[test]
+fn test_lower_refinements() {
+    let loc = |start, end| Loc {
+        start,
+        end,
+        ..Loc::default()
+    };
+    assert_lowers(vec![
+        ("x I32\nx U16\nx 42", "(x 42)"),
+        ("x I32\ny x\nx 1", "(x 1, y x)"),
+        ("P (a I32)\nP (a 1, b I32)", "(P (a 1, b I32))"),
+        ("f(x _) x\ny _\ny f", "(f(x _) x, y f)"),
+        ("V (x I32)\nv V\nv V(1)", "(V (x I32), v V(1))"),
+        ("x []I32\nx [1, 2]", "(x [1, 2])"),
+    ]);
+    assert_eq!(
+        lower_source("x U16\nx 42\nx I32"),
+        Err(LowerError::InvalidRefinement {
+            name: "x".into(),
+            previous: loc(6, 10),
+            new: loc(11, 16),
+        })
+    );
+    assert!(matches!(
+        lower_source("x U8\nx 300"),
+        Err(LowerError::InvalidRefinement { .. })
+    ));
+    assert!(matches!(
+        lower_source("x String\nx 'c'"),
+        Err(LowerError::InvalidRefinement { .. })
+    ));
+    assert!(matches!(
+        lower_source("V (x I32)\nW (x I32)\nv V\nv W(1)"),
+        Err(LowerError::InvalidRefinement { .. })
+    ));
+    assert!(matches!(
+        lower_source("x []U8\nx [1, 300]"),
+        Err(LowerError::InvalidRefinement { .. })
+    ));
+}
+
+#[test]
+fn test_lower_inline() {
+    let loc = |start, end| Loc {
+        start,
+        end,
+        ..Loc::default()
+    };
+    assert_lowers(vec![
+        ("..(a 1, b a)\nc b", "(a 1, b a, c b)"),
+        ("P (x I32)\n..P", "(P (x I32), x P.x)"),
+        (
+            "std (io (print_line(s String) s))\n..std.io",
+            "(std (io (print_line(s String) s)), print_line std.io.print_line)",
+        ),
+        (
+            "V (x I32, y I32)\n..V(1, 2)",
+            "(V (x I32, y I32), x V(1, 2).x, y V(1, 2).y)",
+        ),
+        (
+            "V (x I32, len() 1)\n....V(x 1)",
+            "(V (x I32, len() 1), x ..V(x 1).x)",
+        ),
+        (
+            "m (V (x I32))\n..m\nV:get() x",
+            "(m (V (x I32)), V m.V, get(self V) self.x)",
+        ),
+    ]);
+    assert_eq!(
+        lower_source("a (x 1)\n..a\nx 2"),
+        Err(LowerError::ConflictingInline {
+            name: "x".into(),
+            first: loc(8, 11),
+            second: loc(12, 15),
+        })
+    );
+    assert!(matches!(
+        lower_source("a (f() 1)\nb (f() 2)\n..a\n..b"),
+        Err(LowerError::ConflictingInline { name, .. }) if &*name == "f"
+    ));
+    assert!(matches!(
+        lower_source("..x"),
+        Err(LowerError::UnsupportedInline { .. })
+    ));
+}
+
+#[test]
+fn test_lower_partial_inline() {
+    assert_lowers(vec![
+        (
+            "std (show(x _) \"s\", print(x _) x)\n..std.(show)",
+            "(std (show(x _) \"s\", print(x _) x), show std.show)",
+        ),
+        (
+            "a (f() 1)\nb (f() 2)\n..a.(f)\n..b.(g f)",
+            "(a (f() 1), b (f() 2), f a.f, g b.f)",
+        ),
+        (
+            "m (V (x I32), W (y I32))\n..m.(V)\nV:get() x",
+            "(m (V (x I32), W (y I32)), V m.V, get(self V) self.x)",
+        ),
+    ]);
+    assert_eq!(
+        lower_source("std (a 1)\n..std.(a, b)"),
+        Err(LowerError::UnknownInlineName {
+            name: "b".into(),
+            meta: Loc {
+                start: 20,
+                end: 21,
+                ..Loc::default()
+            },
+        })
+    );
+    assert!(matches!(
+        lower_source("a (f() 1)\nb (f() 2)\n..a.(f)\n..b.(f)"),
+        Err(LowerError::ConflictingInline { name, .. }) if &*name == "f"
+    ));
+    assert!(matches!(
+        lower_source("m (V (x I32))\n..m.(W)\nW:get() 1"),
+        Err(LowerError::UnknownInlineName { name, .. }) if &*name == "W"
+    ));
+}
@@ -1,15 +1,24 @@
+pub mod literal;
 #[cfg(test)]
 mod tests;
 
+use std::ops::Range;
+
 use logos::Logos;
 
+use literal::{LiteralError, Piece};
+
 #[allow(missing_docs)]
 #[derive(Logos, Clone, Copy, Debug, PartialEq)]
 #[logos(skip r"[ \t]+")]
+#[logos(skip r"--([^\-\n][^\n]*)?|----[^\n]*")]
 pub enum Token {
     #[regex(r"[\n\f\r]+")]
     Newline,
 
+    #[regex(r"---([^\-\n][^\n]*)?")]
+    DocComment,
+
     #[token(r",")]
     Comma,
 
@@ -22,6 +31,12 @@ pub enum Token {
     #[token(r"!=")]
     NotEquals,
 
+    #[token(r"!")]
+    Bang,
+
+    #[token(r"=>")]
+    FatArrow,
+
     #[token(r"..")]
     Dots,
 
@@ -34,6 +49,9 @@ pub enum Token {
     #[token(r"::")]
     ColonColon,
 
+    #[token(r":=")]
+    ColonEquals,
+
     #[token(r";")]
     Semicolon,
 
@@ -46,27 +64,57 @@ pub enum Token {
     #[token(r"|")]
     Bar,
 
+    #[token(r"||")]
+    BarBar,
+
+    #[token(r"&")]
+    Ampersand,
+
+    #[token(r"&&")]
+    AmpersandAmpersand,
+
     #[token(r"+")]
     Plus,
 
     #[token(r"++")]
     PlusPlus,
 
+    #[token(r"+=")]
+    PlusEquals,
+
+    #[token(r"++=")]
+    PlusPlusEquals,
+
     #[token(r"-")]
     Minus,
 
+    #[token(r"-=")]
+    MinusEquals,
+
     #[token(r"*")]
     Times,
 
     #[token(r"**")]
     TimesTimes,
 
+    #[token(r"*=")]
+    TimesEquals,
+
     #[token(r"/")]
     Divide,
 
     #[token(r"//")]
     DivideDivide,
 
+    #[token(r"/=")]
+    DivideEquals,
+
+    #[token(r"^")]
+    Caret,
+
+    #[token(r"^=")]
+    CaretEquals,
+
     #[token(r"<<")]
     LShift,
 
@@ -100,44 +148,229 @@ pub enum Token {
     #[token(r">")]
     RAngle,
 
-    #[regex(r"[\d]+(\.[\d]+)?")]
+    #[token(r"<=")]
+    LessEquals,
+
+    #[token(r">=")]
+    GreaterEquals,
+
+    #[regex(r"[\d][\d_]*(\.[\d][\d_]*)?([eE][+\-]?[\d][\d_]*)?([iu](8|16|32|64)|f(32|64))?")]
+    #[regex(r"0x[\da-fA-F_]+([iu](8|16|32|64))?")]
+    #[regex(r"0o[0-7_]+([iu](8|16|32|64))?")]
+    #[regex(r"0b[01_]+([iu](8|16|32|64))?")]
     Number,
 
     #[regex(r"[_a-zA-Z]+[_a-zA-Z0-9]*")]
     Ident,
 
-    #[regex(r#"["]([^"\\\n]|\\.|\\\n)*["]"#)]
+    #[token("\"", lex_string)]
     String,
 
     #[regex(r#"[']([^'\\\n]|\\.|\\\n)*[']"#)]
     Character,
 }
 
+/// Consumes the remainder of a string literal, whose interpolated expressions
+/// may themselves contain string literals
+///
+/// An unterminated string literal consumes the rest of the line.
+fn lex_string(lexer: &mut logos::Lexer<Token>) -> bool {
+    let source = &lexer.source()[lexer.span().start..];
+    match literal::string_len(source) {
+        Some(len) => {
+            lexer.bump(len - 1);
+            true
+        }
+        None => {
+            lexer.bump(source.find('\n').unwrap_or(source.len()) - 1);
+            false
+        }
+    }
+}
+
+/// A region of source code covered by a [Token]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset one past the last character
+    pub end: usize,
+    /// One-based line of the first character
+    pub line: usize,
+    /// One-based column of the first character, counted in characters
+    pub col: usize,
+}
+
+impl Span {
+    /// Computes the [Span] of the byte range `start..end` within `source`
+    pub fn locate(source: &str, start: usize, end: usize) -> Span {
+        Cursor::default().locate(source, start..end)
+    }
+
+    /// Returns the smallest span covering both `self` and `other`
+    pub fn join(self, other: Span) -> Span {
+        if other.start < self.start {
+            return other.join(self);
+        }
+        Span {
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+
+    /// Returns the length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Identifies the source file a [Lexer] is reading from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileId(pub usize);
+
+/// Tracks the line of a position moving forward through source code, so
+/// locating each token only scans the text since the previous one
+#[derive(Clone, Copy)]
+struct Cursor {
+    line: usize,
+    line_start: usize,
+    offset: usize,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            line: 1,
+            line_start: 0,
+            offset: 0,
+        }
+    }
+}
+
+impl Cursor {
+    /// Computes the line and column of a byte range, which must not precede
+    /// any previously located range
+    fn locate(&mut self, source: &str, range: Range<usize>) -> Span {
+        for (i, c) in source[self.offset..range.start].char_indices() {
+            if c == '\n' {
+                self.line += 1;
+                self.line_start = self.offset + i + 1;
+            }
+        }
+        self.offset = range.start;
+        Span {
+            start: range.start,
+            end: range.end,
+            line: self.line,
+            col: source[self.line_start..range.start].chars().count() + 1,
+        }
+    }
+}
+
 /// A lexer wrapping the [logos::Lexer] type
+#[derive(Clone)]
 pub struct Lexer<'source> {
     lexer: logos::Lexer<'source, Token>,
-    next: Option<Token>,
+    file: FileId,
+    pending: Option<(Result<Token, ()>, Range<usize>)>,
+    next: Option<Result<(Token, Span), UnrecognizedTokenError>>,
+    next_slice: &'source str,
     slice: &'source str,
+    span: Span,
+    cursor: Cursor,
+}
+
+/// A piece of an interpolated [Token::String]
+pub enum Segment<'source> {
+    /// Decoded literal text
+    Text(String),
+    /// The tokens of an interpolated expression
+    Expr(Lexer<'source>),
 }
 
 /// An unrecognized token was encountered
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct UnrecognizedTokenError;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnrecognizedTokenError {
+    /// The offending source text
+    pub text: String,
+    /// The location of the offending source text
+    pub span: Span,
+}
 
 impl<'source> Lexer<'source> {
     /// Constructs a new [Lexer] from the given source code
-    pub fn new(source: &'source str) -> Result<Self, UnrecognizedTokenError> {
-        let mut lexer = Token::lexer(source);
-
-        Ok(Self {
-            next: match lexer.next() {
-                Some(Ok(next)) => Some(next),
-                Some(Err(_)) => return Err(UnrecognizedTokenError),
-                None => None,
-            },
-            slice: lexer.slice(),
-            lexer,
-        })
+    pub fn new(source: &'source str) -> Self {
+        Self::with_file(source, FileId::default())
+    }
+
+    /// Constructs a new [Lexer] from the given source code belonging to `file`
+    pub fn with_file(source: &'source str, file: FileId) -> Self {
+        Self::bounded(source, file, 0..source.len())
+    }
+
+    /// Constructs a new [Lexer] over the byte range `range` of `source`, with
+    /// spans relative to the start of `source`
+    fn bounded(source: &'source str, file: FileId, range: Range<usize>) -> Self {
+        let mut lexer = Self {
+            lexer: Token::lexer(&source[..range.end]),
+            file,
+            pending: None,
+            next: None,
+            next_slice: "",
+            slice: "",
+            span: Span::default(),
+            cursor: Cursor::default(),
+        };
+        lexer.lexer.bump(range.start);
+        lexer.advance();
+        lexer
+    }
+
+    /// Splits the most recently consumed [Token::String] into its literal text
+    /// and the tokens of its interpolated expressions
+    pub fn segments(&self) -> Result<Vec<Segment<'source>>, Vec<LiteralError>> {
+        let source = self.lexer.source();
+        let start = self.span.start;
+        Ok(literal::decode_template(self.slice, self.span)?
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => Segment::Text(text),
+                Piece::Expr(range) => Segment::Expr(Self::bounded(
+                    source,
+                    self.file,
+                    start + range.start..start + range.end,
+                )),
+            })
+            .collect())
+    }
+
+    /// Consumes the lexer, separating the valid tokens from every lexical
+    /// error in the source code
+    pub fn tokenize(self) -> (Vec<(Token, Span)>, Vec<UnrecognizedTokenError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for token in self {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(e) => errors.push(e),
+            }
+        }
+        (tokens, errors)
+    }
+
+    /// Returns the [FileId] of the source code
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    /// Returns the full source code
+    pub fn source(&self) -> &'source str {
+        self.lexer.source()
     }
 
     /// Returns the source code's string slice
@@ -145,24 +378,112 @@ impl<'source> Lexer<'source> {
         self.slice
     }
 
+    /// Returns the [Span] of the most recently consumed [Token]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     /// Peeks the next [Token] without consuming it
     pub fn peek(&self) -> Option<Token> {
-        self.next
+        match self.next {
+            Some(Ok((token, _))) => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Peeks the [Span] of the next [Token] or unrecognized token without
+    /// consuming it
+    pub fn peek_span(&self) -> Option<Span> {
+        match &self.next {
+            Some(Ok((_, span))) => Some(*span),
+            Some(Err(e)) => Some(e.span),
+            None => None,
+        }
+    }
+
+    /// Consumes the first `>` of a [Token::RShift] about to be consumed,
+    /// leaving the second as a [Token::RAngle], returning `false` if the next
+    /// token is not a [Token::RShift]
+    ///
+    /// This lets `>>` close two nested angle lists, as in `D<L<I32>>`.
+    pub fn split_rshift(&mut self) -> bool {
+        let Some(Ok((Token::RShift, span))) = self.next else {
+            return false;
+        };
+        self.slice = &self.next_slice[..1];
+        self.next_slice = &self.next_slice[1..];
+        self.span = Span {
+            end: span.start + 1,
+            ..span
+        };
+        self.next = Some(Ok((
+            Token::RAngle,
+            Span {
+                start: span.start + 1,
+                col: span.col + 1,
+                ..span
+            },
+        )));
+        true
+    }
+
+    /// Buffers the next token, merging adjacent unrecognized characters into
+    /// a single error
+    fn advance(&mut self) {
+        let Some((result, range)) = self.pending.take().or_else(|| self.pull()) else {
+            self.next = None;
+            return;
+        };
+        let source = self.lexer.source();
+        match result {
+            Ok(token) => {
+                self.next_slice = &source[range.clone()];
+                self.next = Some(Ok((token, self.locate(range))));
+            }
+            Err(()) => {
+                let mut end = range.end;
+                loop {
+                    match self.pull() {
+                        Some((Err(()), next)) if next.start == end => end = next.end,
+                        pending => {
+                            self.pending = pending;
+                            break;
+                        }
+                    }
+                }
+                self.next_slice = &source[range.start..end];
+                self.next = Some(Err(UnrecognizedTokenError {
+                    text: self.next_slice.to_owned(),
+                    span: self.locate(range.start..end),
+                }));
+            }
+        }
+    }
+
+    /// Pulls the next raw result from the underlying [logos::Lexer]
+    fn pull(&mut self) -> Option<(Result<Token, ()>, Range<usize>)> {
+        let result = self.lexer.next()?;
+        Some((result, self.lexer.span()))
+    }
+
+    /// Computes the line and column of a byte range, which must not precede
+    /// any previously located range
+    fn locate(&mut self, range: Range<usize>) -> Span {
+        self.cursor.locate(self.lexer.source(), range)
     }
 }
 
 impl Iterator for Lexer<'_> {
-    type Item = Result<Token, UnrecognizedTokenError>;
+    type Item = Result<(Token, Span), UnrecognizedTokenError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.slice = self.lexer.slice();
-
-        let next = self.next;
-        self.next = match self.lexer.next() {
-            Some(Ok(next)) => Some(next),
-            Some(Err(_)) => return Some(Err(UnrecognizedTokenError)),
-            None => None,
+        let next = self.next.take()?;
+        self.slice = self.next_slice;
+        self.span = match &next {
+            Ok((_, span)) => *span,
+            Err(e) => e.span,
         };
-        next.map(Ok)
+        self.advance();
+        Some(next)
     }
 }
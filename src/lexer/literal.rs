@@ -0,0 +1,432 @@
+use std::ops::Range;
+
+use super::Span;
+
+/// A decoded numeric literal, typed by its suffix
+///
+/// Integer literals without a suffix are [Number::I32], and float literals
+/// without a suffix are [Number::F32].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+}
+
+impl Number {
+    /// Returns the name of the literal's type, e.g. `"U8"`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Number::I8(_) => "I8",
+            Number::I16(_) => "I16",
+            Number::I32(_) => "I32",
+            Number::I64(_) => "I64",
+            Number::U8(_) => "U8",
+            Number::U16(_) => "U16",
+            Number::U32(_) => "U32",
+            Number::U64(_) => "U64",
+            Number::F32(_) => "F32",
+            Number::F64(_) => "F64",
+        }
+    }
+}
+
+/// A literal could not be decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiteralError {
+    pub kind: LiteralErrorKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LiteralErrorKind {
+    /// A radix prefix such as `0x` is followed only by separators
+    MissingDigits,
+    /// The value does not fit in the named type
+    Overflow(&'static str),
+    /// An integer suffix was given to a float literal
+    InvalidSuffix(String),
+    /// A backslash was followed by an unsupported character
+    UnknownEscape(char),
+    /// A `\u{...}` escape is missing braces or hexadecimal digits
+    MalformedUnicodeEscape,
+    /// A `\u{...}` escape does not name a Unicode scalar value
+    InvalidCodepoint(u32),
+    /// A character literal contains no characters
+    EmptyChar,
+    /// A character literal contains more than one character
+    MultipleChars,
+    /// A string literal that may not be interpolated contains an expression
+    UnexpectedInterpolation,
+    /// An interpolation such as `{ }` contains no expression
+    EmptyInterpolation,
+}
+
+/// A piece of a string literal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Piece {
+    /// Decoded literal text
+    Text(String),
+    /// The byte range of an interpolated expression within the literal,
+    /// excluding its braces
+    Expr(Range<usize>),
+}
+
+/// Decodes the source text of a [super::Token::Number] into a typed value
+///
+/// Literals never include a sign, since `-` is parsed as negation, so the
+/// minimum value of a signed type such as `-2147483648` cannot be written as
+/// a literal. It can be computed instead, as in `-2147483647 - 1`.
+pub fn decode_number(slice: &str, span: Span) -> Result<Number, LiteralError> {
+    let (radix, body) = match slice.get(..2) {
+        Some("0x") => (16, &slice[2..]),
+        Some("0o") => (8, &slice[2..]),
+        Some("0b") => (2, &slice[2..]),
+        _ => (10, slice),
+    };
+
+    // hexadecimal digits may contain `f`, but hexadecimal literals never
+    // accept float suffixes, so the suffix always starts at an `i` or `u`
+    let suffix_start = match radix {
+        10 => body.find(['i', 'u', 'f']),
+        _ => body.find(['i', 'u']),
+    };
+    let (digits, suffix) = match suffix_start {
+        Some(i) => (&body[..i], Some(&body[i..])),
+        None => (body, None),
+    };
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    let is_float = radix == 10 && digits.contains(['.', 'e', 'E']);
+
+    let error = |kind| LiteralError { kind, span };
+    let overflow = |ty| error(LiteralErrorKind::Overflow(ty));
+    if digits.is_empty() {
+        return Err(error(LiteralErrorKind::MissingDigits));
+    }
+
+    match (is_float, suffix) {
+        (_, Some("f32")) | (true, None) => {
+            let value: f64 = digits.parse().map_err(|_| overflow("F32"))?;
+            let value = value as f32;
+            match value.is_finite() {
+                true => Ok(Number::F32(value)),
+                false => Err(overflow("F32")),
+            }
+        }
+        (_, Some("f64")) => {
+            let value: f64 = digits.parse().map_err(|_| overflow("F64"))?;
+            match value.is_finite() {
+                true => Ok(Number::F64(value)),
+                false => Err(overflow("F64")),
+            }
+        }
+        (true, Some(suffix)) => Err(error(LiteralErrorKind::InvalidSuffix(suffix.to_owned()))),
+        (false, suffix) => {
+            let value = u64::from_str_radix(&digits, radix);
+            let suffix = suffix.unwrap_or("i32");
+            let ty = match suffix {
+                "i8" => "I8",
+                "i16" => "I16",
+                "i32" => "I32",
+                "i64" => "I64",
+                "u8" => "U8",
+                "u16" => "U16",
+                "u32" => "U32",
+                "u64" => "U64",
+                _ => return Err(error(LiteralErrorKind::InvalidSuffix(suffix.to_owned()))),
+            };
+            let value = value.map_err(|_| overflow(ty))?;
+            let number = match suffix {
+                "i8" => value.try_into().map(Number::I8),
+                "i16" => value.try_into().map(Number::I16),
+                "i32" => value.try_into().map(Number::I32),
+                "i64" => value.try_into().map(Number::I64),
+                "u8" => value.try_into().map(Number::U8),
+                "u16" => value.try_into().map(Number::U16),
+                "u32" => value.try_into().map(Number::U32),
+                _ => Ok(Number::U64(value)),
+            };
+            number.map_err(|_| overflow(ty))
+        }
+    }
+}
+
+/// Decodes the source text of a [super::Token::String] without interpolated
+/// expressions, including its quotes
+pub fn decode_string(slice: &str, span: Span) -> Result<String, Vec<LiteralError>> {
+    let mut decoded = String::new();
+    for piece in decode_template(slice, span)? {
+        match piece {
+            Piece::Text(text) => decoded += &text,
+            Piece::Expr(range) => {
+                return Err(vec![LiteralError {
+                    kind: LiteralErrorKind::UnexpectedInterpolation,
+                    span: sub_span(slice, span, range.start - 1, range.end + 1),
+                }]);
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+/// Splits the source text of a [super::Token::String], including its quotes,
+/// into decoded text and the ranges of `{...}` interpolated expressions
+///
+/// Adjacent text is merged into a single [Piece::Text], and `{{` and `}}`
+/// decode to literal braces.
+pub fn decode_template(slice: &str, span: Span) -> Result<Vec<Piece>, Vec<LiteralError>> {
+    let bytes = slice.as_bytes();
+    let end = slice.len() - 1;
+    let mut pieces = Vec::new();
+    let mut errors = Vec::new();
+    let mut text = String::new();
+    let mut text_start = 1;
+    let mut i = 1;
+    while i < end {
+        let brace = match (bytes[i], bytes.get(i + 1)) {
+            (b'\\', _) => {
+                i += escape_len(bytes, i);
+                continue;
+            }
+            (b'{', Some(b'{')) | (b'}', Some(b'}')) => Some(bytes[i] as char),
+            (b'{', _) => None,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        match unescape(slice, text_start, i, span) {
+            Ok(decoded) => text += &decoded,
+            Err(e) => errors.extend(e),
+        }
+        match brace {
+            Some(brace) => {
+                text.push(brace);
+                i += 2;
+            }
+            None => {
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                let len = interpolation_len(&slice[i + 1..end]).unwrap_or(end - i - 1);
+                match slice[i + 1..i + 1 + len].trim().is_empty() {
+                    true => errors.push(LiteralError {
+                        kind: LiteralErrorKind::EmptyInterpolation,
+                        span: sub_span(slice, span, i, (i + len + 2).min(end)),
+                    }),
+                    false => pieces.push(Piece::Expr(i + 1..i + 1 + len)),
+                }
+                i += len + 2;
+            }
+        }
+        text_start = i;
+    }
+    match unescape(slice, text_start.min(end), end, span) {
+        Ok(decoded) => text += &decoded,
+        Err(e) => errors.extend(e),
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    match errors.is_empty() {
+        true => Ok(pieces),
+        false => Err(errors),
+    }
+}
+
+/// Returns the length of the string literal at the start of `source`,
+/// including both quotes, or [None] if it is unterminated
+pub fn string_len(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => return Some(i + 1),
+            (b'\n', _) => return None,
+            (b'\\', _) => i += escape_len(bytes, i),
+            (b'{', Some(b'{')) => i += 2,
+            (b'{', _) => i += interpolation_len(&source[i + 1..])? + 2,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the length of the interpolated expression at the start of
+/// `source`, up to but excluding its closing brace
+fn interpolation_len(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i += string_len(&source[i..])?,
+            b'\'' => i += char_len(&source[i..])?,
+            b'\n' => return None,
+            b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' if depth == 0 => return Some(i),
+            b'}' => {
+                depth -= 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the length of the escape sequence starting with the backslash at
+/// `bytes[i]`, so that the braces of `\u{...}` are not read as an interpolation
+fn escape_len(bytes: &[u8], i: usize) -> usize {
+    if bytes.get(i + 1) != Some(&b'u') || bytes.get(i + 2) != Some(&b'{') {
+        return 2;
+    }
+    let mut len = 3;
+    while let Some(&b) = bytes.get(i + len) {
+        match b {
+            b'}' => return len + 1,
+            b'"' | b'\'' | b'\n' | b'\\' => return len,
+            _ => len += 1,
+        }
+    }
+    len
+}
+
+/// Returns the length of the character literal at the start of `source`,
+/// including both quotes, or [None] if it is unterminated
+fn char_len(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => return Some(i + 1),
+            b'\n' => return None,
+            b'\\' => i += escape_len(bytes, i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Decodes the source text of a [super::Token::Character], including its
+/// quotes
+pub fn decode_char(slice: &str, span: Span) -> Result<char, Vec<LiteralError>> {
+    let decoded = unescape(slice, 1, slice.len() - 1, span)?;
+    let mut chars = decoded.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        (None, _) => Err(vec![LiteralError {
+            kind: LiteralErrorKind::EmptyChar,
+            span,
+        }]),
+        (Some(_), Some(_)) => Err(vec![LiteralError {
+            kind: LiteralErrorKind::MultipleChars,
+            span,
+        }]),
+    }
+}
+
+/// Decodes the escape sequences in `slice[start..end]`, where `slice` is the
+/// source text of the literal starting at `span`
+///
+/// Every malformed escape is reported, each with the span of the escape
+/// itself.
+pub fn unescape(
+    slice: &str,
+    start: usize,
+    end: usize,
+    span: Span,
+) -> Result<String, Vec<LiteralError>> {
+    let mut decoded = String::new();
+    let mut errors = Vec::new();
+    let mut chars = slice[start..end].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        let error = |kind, len| LiteralError {
+            kind,
+            span: sub_span(slice, span, start + i, start + i + len),
+        };
+        let Some((_, escape)) = chars.next() else {
+            errors.push(error(LiteralErrorKind::UnknownEscape('\\'), 1));
+            break;
+        };
+        match escape {
+            'n' => decoded.push('\n'),
+            'r' => decoded.push('\r'),
+            't' => decoded.push('\t'),
+            '0' => decoded.push('\0'),
+            '\\' | '"' | '\'' => decoded.push(escape),
+            '\n' => {
+                // line continuation: skip the newline and any indentation
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            }
+            'u' => {
+                let mut len = 2;
+                let mut digits = String::new();
+                let mut closed = false;
+                if chars.next_if(|(_, c)| *c == '{').is_some() {
+                    len += 1;
+                    while let Some((_, c)) = chars.next_if(|(_, c)| *c != '"' && *c != '\\') {
+                        len += c.len_utf8();
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        digits.push(c);
+                    }
+                }
+                let codepoint = match closed && (1..=6).contains(&digits.len()) {
+                    true => u32::from_str_radix(&digits, 16).ok(),
+                    false => None,
+                };
+                match codepoint.map(|n| (n, char::from_u32(n))) {
+                    Some((_, Some(c))) => decoded.push(c),
+                    Some((n, None)) => {
+                        errors.push(error(LiteralErrorKind::InvalidCodepoint(n), len))
+                    }
+                    None => errors.push(error(LiteralErrorKind::MalformedUnicodeEscape, len)),
+                }
+            }
+            _ => errors.push(error(
+                LiteralErrorKind::UnknownEscape(escape),
+                1 + escape.len_utf8(),
+            )),
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(decoded),
+        false => Err(errors),
+    }
+}
+
+/// Computes the [Span] of `slice[start..end]`, where `slice` is the source text
+/// starting at `span`
+fn sub_span(slice: &str, span: Span, start: usize, end: usize) -> Span {
+    let before = &slice[..start];
+    let (line, col) = match before.rfind('\n') {
+        Some(i) => (
+            span.line + before.matches('\n').count(),
+            before[i + 1..].chars().count() + 1,
+        ),
+        None => (span.line, span.col + before.chars().count()),
+    };
+    Span {
+        start: span.start + start,
+        end: span.start + end,
+        line,
+        col,
+    }
+}
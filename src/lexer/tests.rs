@@ -1,22 +1,35 @@
 use super::*;
 
 fn lex_str(source: &str) -> Vec<Result<Token, UnrecognizedTokenError>> {
-    let lexer = Lexer::new(source)
-        .unwrap_or_else(|_| panic!("unable to construct lexer for source {source}"));
-    let tokens: Vec<Result<Token, UnrecognizedTokenError>> = lexer.collect();
+    let lexer = Lexer::new(source);
+    let tokens: Vec<Result<Token, UnrecognizedTokenError>> =
+        lexer.map(|tok| tok.map(|(tok, _)| tok)).collect();
     tokens
 }
 
 fn lex_str_ok(source: &str) -> Vec<Token> {
-    let lexer = Lexer::new(source)
-        .unwrap_or_else(|_| panic!("unable to construct lexer for source {source}"));
+    let lexer = Lexer::new(source);
     let tokens: Vec<Token> = lexer
         .into_iter()
-        .map(|tok| tok.expect("illegal token"))
+        .map(|tok| tok.expect("illegal token").0)
         .collect();
     tokens
 }
 
+fn lex_str_spans(source: &str) -> Vec<(Token, Span)> {
+    let lexer = Lexer::new(source);
+    lexer.map(|tok| tok.expect("illegal token")).collect()
+}
+
+fn span(start: usize, end: usize, line: usize, col: usize) -> Span {
+    Span {
+        start,
+        end,
+        line,
+        col,
+    }
+}
+
 #[test]
 fn test_empty() {
     assert_eq!(lex_str("").len(), 0);
@@ -30,21 +43,34 @@ fn test_repeated_tokens() {
         ("=", Token::Equals, 1, false),
         ("==", Token::EqualsEquals, 4, false),
         ("!=", Token::NotEquals, 4, false),
+        ("!", Token::Bang, 4, false),
+        ("=>", Token::FatArrow, 4, false),
         ("..", Token::Dots, 4, false),
         (".", Token::Dot, 1, false),
         (":", Token::Colon, 1, false),
         ("::", Token::ColonColon, 4, false),
+        (":=", Token::ColonEquals, 4, false),
         (";", Token::Semicolon, 4, false),
         ("?", Token::QuestionMark, 4, false),
         ("->", Token::Arrow, 4, false),
-        ("|", Token::Bar, 4, false),
+        ("|", Token::Bar, 1, false),
+        ("||", Token::BarBar, 4, false),
+        ("&", Token::Ampersand, 1, false),
+        ("&&", Token::AmpersandAmpersand, 4, false),
         ("+", Token::Plus, 1, false),
         ("++", Token::PlusPlus, 4, false),
+        ("+=", Token::PlusEquals, 4, false),
+        ("++=", Token::PlusPlusEquals, 4, false),
         ("-", Token::Minus, 1, false),
+        ("-=", Token::MinusEquals, 4, false),
         ("*", Token::Times, 1, false),
         ("**", Token::TimesTimes, 4, false),
+        ("*=", Token::TimesEquals, 4, false),
         ("/", Token::Divide, 1, false),
         ("//", Token::DivideDivide, 4, false),
+        ("/=", Token::DivideEquals, 4, false),
+        ("^", Token::Caret, 4, false),
+        ("^=", Token::CaretEquals, 4, false),
         ("%", Token::Percent, 4, false),
         ("<<", Token::LShift, 4, false),
         (">>", Token::RShift, 4, false),
@@ -56,6 +82,8 @@ fn test_repeated_tokens() {
         ("]", Token::RSquare, 4, false),
         ("<", Token::LAngle, 1, false),
         (">", Token::RAngle, 1, false),
+        ("<=", Token::LessEquals, 4, false),
+        (">=", Token::GreaterEquals, 4, false),
     ];
     for (tok_str, tok, count, condense) in tokens {
         for n in 1..count {
@@ -68,3 +96,572 @@ fn test_repeated_tokens() {
         }
     }
 }
+
+#[test]
+fn test_spans() {
+    let actual = lex_str_spans("x = 42\n  foo(y)");
+    let expected = vec![
+        (Token::Ident, span(0, 1, 1, 1)),
+        (Token::Equals, span(2, 3, 1, 3)),
+        (Token::Number, span(4, 6, 1, 5)),
+        (Token::Newline, span(6, 7, 1, 7)),
+        (Token::Ident, span(9, 12, 2, 3)),
+        (Token::LParen, span(12, 13, 2, 6)),
+        (Token::Ident, span(13, 14, 2, 7)),
+        (Token::RParen, span(14, 15, 2, 8)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_spans_multiple_newlines() {
+    let actual = lex_str_spans("a\n\n\nb");
+    assert_eq!(
+        actual,
+        vec![
+            (Token::Ident, span(0, 1, 1, 1)),
+            (Token::Newline, span(1, 4, 1, 2)),
+            (Token::Ident, span(4, 5, 4, 1)),
+        ]
+    );
+}
+
+#[test]
+fn test_spans_unicode_columns() {
+    let actual = lex_str_spans("\"h\u{e9}llo\" x");
+    assert_eq!(actual[1], (Token::Ident, span(9, 10, 1, 9)));
+}
+
+#[test]
+fn test_peek_span() {
+    let mut lexer = Lexer::new("a\n  b");
+    assert_eq!(lexer.peek(), Some(Token::Ident));
+    assert_eq!(lexer.peek_span(), Some(span(0, 1, 1, 1)));
+    lexer.next();
+    lexer.next();
+    assert_eq!(lexer.peek_span(), Some(span(4, 5, 2, 3)));
+    let (tok, tok_span) = lexer.next().unwrap().unwrap();
+    assert_eq!(tok, Token::Ident);
+    assert_eq!(lexer.span(), tok_span);
+    assert_eq!(lexer.slice(), "b");
+    assert_eq!(lexer.peek_span(), None);
+}
+
+#[test]
+fn test_combined_tokens() {
+    use Token::*;
+    let cases: &[(&str, &[Token])] = &[
+        ("+++", &[PlusPlus, Plus]),
+        ("+==", &[PlusEquals, Equals]),
+        ("++=+", &[PlusPlusEquals, Plus]),
+        ("+++=", &[PlusPlus, PlusEquals]),
+        ("++==", &[PlusPlusEquals, Equals]),
+        ("|||", &[BarBar, Bar]),
+        ("&&&", &[AmpersandAmpersand, Ampersand]),
+        ("!==", &[NotEquals, Equals]),
+        ("!!=", &[Bang, NotEquals]),
+        ("==>", &[EqualsEquals, RAngle]),
+        ("=>=", &[FatArrow, Equals]),
+        ("=>>", &[FatArrow, RAngle]),
+        ("<=>", &[LessEquals, RAngle]),
+        (">==", &[GreaterEquals, Equals]),
+        ("<<=", &[LShift, Equals]),
+        (">>=", &[RShift, Equals]),
+        ("::=", &[ColonColon, Equals]),
+        (":==", &[ColonEquals, Equals]),
+        ("^^=", &[Caret, CaretEquals]),
+        ("**=", &[TimesTimes, Equals]),
+        ("*==", &[TimesEquals, Equals]),
+        ("//=", &[DivideDivide, Equals]),
+        ("-=-", &[MinusEquals, Minus]),
+        ("->=", &[Arrow, Equals]),
+        ("x^2", &[Ident, Caret, Number]),
+        ("x /= len", &[Ident, DivideEquals, Ident]),
+        ("my_list ++= x", &[Ident, PlusPlusEquals, Ident]),
+        ("=> x", &[FatArrow, Ident]),
+        (
+            "Vector3&(x 1)",
+            &[Ident, Ampersand, LParen, Ident, Number, RParen],
+        ),
+        ("add!(2)", &[Ident, Bang, LParen, Number, RParen]),
+        (
+            "a <= b && c >= d || !e",
+            &[
+                Ident,
+                LessEquals,
+                Ident,
+                AmpersandAmpersand,
+                Ident,
+                GreaterEquals,
+                Ident,
+                BarBar,
+                Bang,
+                Ident,
+            ],
+        ),
+    ];
+    for (source, expected) in cases {
+        let actual = lex_str_ok(source);
+        if actual != *expected {
+            panic!("tokens not equal for \"{source}\": expected {expected:?}, found {actual:?}");
+        }
+    }
+}
+
+#[test]
+fn test_lex_docs_examples() {
+    for source in [
+        include_str!("../../docs/example.lx"),
+        include_str!("../../docs/dyn_example.lx"),
+    ] {
+        let (_, errors) = Lexer::new(source).tokenize();
+        assert_eq!(errors, vec![]);
+    }
+}
+
+#[test]
+fn test_comments() {
+    use Token::*;
+    let cases: &[(&str, &[Token])] = &[
+        ("-- comment", &[]),
+        ("--", &[]),
+        ("----", &[]),
+        ("---- not a doc comment", &[]),
+        ("x -- comment", &[Ident]),
+        ("x-- comment", &[Ident]),
+        ("a - b", &[Ident, Minus, Ident]),
+        ("a -b", &[Ident, Minus, Ident]),
+        ("a - -b", &[Ident, Minus, Minus, Ident]),
+        ("a --b", &[Ident]),
+        ("a -> b -- comment", &[Ident, Arrow, Ident]),
+        ("a->--b", &[Ident, Arrow]),
+        ("a-->b", &[Ident]),
+        ("-- comment\n-", &[Newline, Minus]),
+        ("-- comment\n->", &[Newline, Arrow]),
+        ("x -- comment\ny", &[Ident, Newline, Ident]),
+        ("--- doc", &[DocComment]),
+        ("---", &[DocComment]),
+        ("--- doc\nx", &[DocComment, Newline, Ident]),
+        ("x --- doc -- not nested", &[Ident, DocComment]),
+        (
+            "--- doc\n-- comment\n--- doc",
+            &[DocComment, Newline, Newline, DocComment],
+        ),
+    ];
+    for (source, expected) in cases {
+        let actual = lex_str_ok(source);
+        if actual != *expected {
+            panic!("tokens not equal for \"{source}\": expected {expected:?}, found {actual:?}");
+        }
+    }
+}
+
+#[test]
+fn test_doc_comment_span() {
+    let source = "-- hidden\n--- shown\nx";
+    let tokens = lex_str_spans(source);
+    assert_eq!(tokens[1], (Token::DocComment, span(10, 19, 2, 1)));
+    assert_eq!(&source[10..19], "--- shown");
+}
+
+fn unrecognized(text: &str, span: Span) -> UnrecognizedTokenError {
+    UnrecognizedTokenError {
+        text: text.to_owned(),
+        span,
+    }
+}
+
+#[test]
+fn test_unrecognized_first_token() {
+    let actual = lex_str("$x");
+    assert_eq!(
+        actual,
+        vec![Err(unrecognized("$", span(0, 1, 1, 1))), Ok(Token::Ident)]
+    );
+}
+
+#[test]
+fn test_unrecognized_recovery() {
+    let actual = lex_str("a $$ b @ c$");
+    assert_eq!(
+        actual,
+        vec![
+            Ok(Token::Ident),
+            Err(unrecognized("$$", span(2, 4, 1, 3))),
+            Ok(Token::Ident),
+            Err(unrecognized("@", span(7, 8, 1, 8))),
+            Ok(Token::Ident),
+            Err(unrecognized("$", span(10, 11, 1, 11))),
+        ]
+    );
+}
+
+#[test]
+fn test_unrecognized_slice_and_span() {
+    let mut lexer = Lexer::new("x\n  $@ y");
+    lexer.next();
+    lexer.next();
+    assert_eq!(lexer.peek(), None);
+    assert_eq!(lexer.peek_span(), Some(span(4, 6, 2, 3)));
+    assert!(lexer.next().unwrap().is_err());
+    assert_eq!(lexer.slice(), "$@");
+    assert_eq!(lexer.span(), span(4, 6, 2, 3));
+    assert_eq!(lexer.peek(), Some(Token::Ident));
+}
+
+#[test]
+fn test_tokenize_collects_all_errors() {
+    let (tokens, errors) = Lexer::new("a = $\nb = `1`\n\u{e9}").tokenize();
+    assert_eq!(
+        tokens.iter().map(|(tok, _)| *tok).collect::<Vec<_>>(),
+        vec![
+            Token::Ident,
+            Token::Equals,
+            Token::Newline,
+            Token::Ident,
+            Token::Equals,
+            Token::Number,
+            Token::Newline,
+        ]
+    );
+    assert_eq!(
+        errors,
+        vec![
+            unrecognized("$", span(4, 5, 1, 5)),
+            unrecognized("`", span(10, 11, 2, 5)),
+            unrecognized("`", span(12, 13, 2, 7)),
+            unrecognized("\u{e9}", span(14, 16, 3, 1)),
+        ]
+    );
+}
+
+fn decode(source: &str) -> Result<literal::Number, literal::LiteralError> {
+    let tokens = lex_str_spans(source);
+    assert_eq!(tokens.len(), 1, "expected a single token for {source}");
+    let (tok, span) = tokens[0];
+    assert_eq!(tok, Token::Number);
+    literal::decode_number(&source[span.start..span.end], span)
+}
+
+#[test]
+fn test_number_literals() {
+    use literal::Number::*;
+    let cases = [
+        ("0", I32(0)),
+        ("42", I32(42)),
+        ("1_000_000", I32(1_000_000)),
+        ("2147483647", I32(i32::MAX)),
+        ("0xff", I32(255)),
+        ("0xFF_FF", I32(0xffff)),
+        ("0o17", I32(0o17)),
+        ("0b1010_1010", I32(0b1010_1010)),
+        ("42u8", U8(42)),
+        ("255u8", U8(255)),
+        ("0xffu8", U8(255)),
+        ("0b1i8", I8(1)),
+        ("65535u16", U16(65535)),
+        ("7i16", I16(7)),
+        ("4294967295u32", U32(u32::MAX)),
+        ("9_223_372_036_854_775_807i64", I64(i64::MAX)),
+        ("18446744073709551615u64", U64(u64::MAX)),
+        ("1.0", F32(1.0)),
+        ("1.5f32", F32(1.5)),
+        ("2.25f64", F64(2.25)),
+        ("1e3", F32(1000.0)),
+        ("1.5e-2", F32(0.015)),
+        ("2E+2_0", F32(2e20)),
+        ("3f64", F64(3.0)),
+        ("1_0.0_1", F32(10.01)),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(decode(source), Ok(expected), "decoding {source}");
+    }
+}
+
+#[test]
+fn test_number_literal_errors() {
+    use literal::LiteralErrorKind::*;
+    let cases = [
+        ("2147483648", Overflow("I32")),
+        ("256u8", Overflow("U8")),
+        ("0x100u8", Overflow("U8")),
+        ("128i8", Overflow("I8")),
+        ("65536u16", Overflow("U16")),
+        ("18446744073709551616u64", Overflow("U64")),
+        ("1e39", Overflow("F32")),
+        ("1e309f64", Overflow("F64")),
+        ("1.5u8", InvalidSuffix("u8".to_owned())),
+        ("1e2i32", InvalidSuffix("i32".to_owned())),
+        ("0x_", MissingDigits),
+        ("0b__", MissingDigits),
+        ("0o_u8", MissingDigits),
+    ];
+    for (source, kind) in cases {
+        let err = decode(source).expect_err(source);
+        assert_eq!(err.kind, kind, "decoding {source}");
+        assert_eq!(err.span, span(0, source.len(), 1, 1));
+    }
+}
+
+#[test]
+fn test_number_token_boundaries() {
+    use Token::*;
+    let cases: &[(&str, &[Token])] = &[
+        ("1.x", &[Number, Dot, Ident]),
+        ("1..2", &[Number, Dots, Number]),
+        ("x.0", &[Ident, Dot, Number]),
+        ("1e", &[Number, Ident]),
+        ("0x", &[Number, Ident]),
+        ("42abc", &[Number, Ident]),
+        ("42u7", &[Number, Ident]),
+        ("1.0:sqrt", &[Number, Colon, Ident]),
+        ("-1.5", &[Minus, Number]),
+        ("x^2.0", &[Ident, Caret, Number]),
+    ];
+    for (source, expected) in cases {
+        let actual = lex_str_ok(source);
+        if actual != *expected {
+            panic!("tokens not equal for \"{source}\": expected {expected:?}, found {actual:?}");
+        }
+    }
+}
+
+fn decode_str(source: &str) -> Result<String, Vec<literal::LiteralError>> {
+    let tokens = lex_str_spans(source);
+    assert_eq!(tokens, vec![(Token::String, span(0, source.len(), 1, 1))]);
+    literal::decode_string(source, tokens[0].1)
+}
+
+#[test]
+fn test_string_escapes() {
+    let cases = [
+        (r#""""#, ""),
+        (r#""plain""#, "plain"),
+        (r#""a\nb""#, "a\nb"),
+        (r#""\t\r\0""#, "\t\r\0"),
+        (r#""\\ \" \'""#, "\\ \" '"),
+        (r#""\u{41}\u{e9}\u{1F600}""#, "A\u{e9}\u{1f600}"),
+        ("\"caf\u{e9}\"", "caf\u{e9}"),
+        ("\"one \\\n    two\"", "one two"),
+        ("\"one\\\n\\\n  two\"", "onetwo"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(
+            decode_str(source),
+            Ok(expected.to_owned()),
+            "decoding {source}"
+        );
+    }
+}
+
+#[test]
+fn test_string_escape_errors() {
+    use literal::LiteralErrorKind::*;
+    let err = |kind, start, end, line, col| literal::LiteralError {
+        kind,
+        span: span(start, end, line, col),
+    };
+    let cases = [
+        (r#""\q""#, vec![err(UnknownEscape('q'), 1, 3, 1, 2)]),
+        (r#""\u41""#, vec![err(MalformedUnicodeEscape, 1, 3, 1, 2)]),
+        (r#""\u{}""#, vec![err(MalformedUnicodeEscape, 1, 5, 1, 2)]),
+        (r#""\u{41""#, vec![err(MalformedUnicodeEscape, 1, 6, 1, 2)]),
+        (r#""\u{zz}""#, vec![err(MalformedUnicodeEscape, 1, 7, 1, 2)]),
+        (
+            r#""\u{1234567}""#,
+            vec![err(MalformedUnicodeEscape, 1, 12, 1, 2)],
+        ),
+        (
+            r#""\u{D800}""#,
+            vec![err(InvalidCodepoint(0xd800), 1, 9, 1, 2)],
+        ),
+        (r#""é\x""#, vec![err(UnknownEscape('x'), 3, 5, 1, 3)]),
+        (
+            "\"a\\q \\\n  \\w\"",
+            vec![
+                err(UnknownEscape('q'), 2, 4, 1, 3),
+                err(UnknownEscape('w'), 9, 11, 2, 3),
+            ],
+        ),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(decode_str(source), Err(expected), "decoding {source}");
+    }
+}
+
+#[test]
+fn test_char_literals() {
+    use literal::LiteralErrorKind::*;
+    let decode_char = |source: &str| {
+        let tokens = lex_str_spans(source);
+        assert_eq!(
+            tokens,
+            vec![(Token::Character, span(0, source.len(), 1, 1))]
+        );
+        literal::decode_char(source, tokens[0].1)
+    };
+    assert_eq!(decode_char("'a'"), Ok('a'));
+    assert_eq!(decode_char("'\u{e9}'"), Ok('\u{e9}'));
+    assert_eq!(decode_char("'\u{1f600}'"), Ok('\u{1f600}'));
+    assert_eq!(decode_char(r"'\''"), Ok('\''));
+    assert_eq!(decode_char(r"'\n'"), Ok('\n'));
+    assert_eq!(decode_char(r"'\u{3bb}'"), Ok('\u{3bb}'));
+    let kind = |source| decode_char(source).unwrap_err()[0].kind.clone();
+    assert_eq!(kind("''"), EmptyChar);
+    assert_eq!(kind("'ab'"), MultipleChars);
+    assert_eq!(kind(r"'\z'"), UnknownEscape('z'));
+}
+
+#[derive(Debug, PartialEq)]
+enum TestSegment {
+    Text(&'static str),
+    Expr(Vec<(Token, Span)>),
+}
+
+fn segments(source: &str) -> Vec<TestSegment> {
+    let mut lexer = Lexer::new(source);
+    let (tok, _) = lexer.next().unwrap().expect("illegal token");
+    assert_eq!(tok, Token::String);
+    assert_eq!(lexer.next(), None);
+    lexer
+        .segments()
+        .expect("malformed string")
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => TestSegment::Text(text.leak()),
+            Segment::Expr(lexer) => {
+                TestSegment::Expr(lexer.map(|tok| tok.expect("illegal token")).collect())
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_interpolation_segments() {
+    use TestSegment::*;
+    assert_eq!(segments(r#""plain""#), vec![Text("plain")]);
+    assert_eq!(segments(r#""""#), vec![]);
+    assert_eq!(
+        segments(r#""({x}, {y})""#),
+        vec![
+            Text("("),
+            Expr(vec![(Token::Ident, span(3, 4, 1, 4))]),
+            Text(", "),
+            Expr(vec![(Token::Ident, span(8, 9, 1, 9))]),
+            Text(")"),
+        ]
+    );
+    assert_eq!(
+        segments(r#""{self.x}""#),
+        vec![Expr(vec![
+            (Token::Ident, span(2, 6, 1, 3)),
+            (Token::Dot, span(6, 7, 1, 7)),
+            (Token::Ident, span(7, 8, 1, 8)),
+        ])]
+    );
+    assert_eq!(
+        segments(r#""{name} (age {age})""#),
+        vec![
+            Expr(vec![(Token::Ident, span(2, 6, 1, 3))]),
+            Text(" (age "),
+            Expr(vec![(Token::Ident, span(14, 17, 1, 15))]),
+            Text(")"),
+        ]
+    );
+}
+
+#[test]
+fn test_interpolation_escaped_braces() {
+    use TestSegment::*;
+    assert_eq!(segments(r#""{{x}}""#), vec![Text("{x}")]);
+    assert_eq!(segments(r#""}""#), vec![Text("}")]);
+    assert_eq!(
+        segments(r#""{{{x}}}\n""#),
+        vec![
+            Text("{"),
+            Expr(vec![(Token::Ident, span(4, 5, 1, 5))]),
+            Text("}\n"),
+        ]
+    );
+    assert_eq!(
+        segments(r#""\\{x}""#),
+        vec![Text("\\"), Expr(vec![(Token::Ident, span(4, 5, 1, 5))])]
+    );
+}
+
+#[test]
+fn test_interpolation_nesting() {
+    use TestSegment::*;
+    assert_eq!(
+        segments(r#""a{ { x } }b""#),
+        vec![
+            Text("a"),
+            Expr(vec![
+                (Token::LCurl, span(4, 5, 1, 5)),
+                (Token::Ident, span(6, 7, 1, 7)),
+                (Token::RCurl, span(8, 9, 1, 9)),
+            ]),
+            Text("b"),
+        ]
+    );
+    assert_eq!(
+        segments(r#""{f("}", '}')}""#),
+        vec![Expr(vec![
+            (Token::Ident, span(2, 3, 1, 3)),
+            (Token::LParen, span(3, 4, 1, 4)),
+            (Token::String, span(4, 7, 1, 5)),
+            (Token::Comma, span(7, 8, 1, 8)),
+            (Token::Character, span(9, 12, 1, 10)),
+            (Token::RParen, span(12, 13, 1, 13)),
+        ])]
+    );
+
+    // interpolations inside of interpolations are split on demand
+    let mut lexer = Lexer::new(r#""<{"[{x}]"}>""#);
+    lexer.next();
+    let mut outer = lexer.segments().unwrap();
+    assert!(matches!(&outer[0], Segment::Text(text) if text == "<"));
+    let Segment::Expr(inner) = &mut outer[1] else {
+        panic!("expected an interpolated expression");
+    };
+    let (tok, tok_span) = inner.next().unwrap().unwrap();
+    assert_eq!((tok, tok_span), (Token::String, span(3, 10, 1, 4)));
+    let inner = inner.segments().unwrap();
+    assert!(matches!(&inner[0], Segment::Text(text) if text == "["));
+    assert!(matches!(&inner[2], Segment::Text(text) if text == "]"));
+}
+
+#[test]
+fn test_unterminated_strings() {
+    assert_eq!(
+        lex_str("x = \"abc\ny"),
+        vec![
+            Ok(Token::Ident),
+            Ok(Token::Equals),
+            Err(unrecognized("\"abc", span(4, 8, 1, 5))),
+            Ok(Token::Newline),
+            Ok(Token::Ident),
+        ]
+    );
+    assert_eq!(
+        lex_str("\"{x\""),
+        vec![Err(unrecognized("\"{x\"", span(0, 4, 1, 1)))]
+    );
+    assert_eq!(
+        lex_str("\"{\"}\"}\" x"),
+        vec![Ok(Token::String), Ok(Token::Ident)]
+    );
+}
+
+#[test]
+fn test_decode_string_rejects_interpolation() {
+    let source = r#""a {b} c""#;
+    let err = literal::decode_string(source, span(0, source.len(), 1, 1)).unwrap_err();
+    assert_eq!(
+        err,
+        vec![literal::LiteralError {
+            kind: literal::LiteralErrorKind::UnexpectedInterpolation,
+            span: span(3, 6, 1, 4),
+        }]
+    );
+}
@@ -0,0 +1,276 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    ast::*,
+    lexer::{FileId, Lexer},
+    node::*,
+    parser::{Loc, ParseError, parse_module},
+};
+
+/// The extension of source files
+pub const EXTENSION: &str = "lx";
+
+/// An error encountered while loading the files of a program
+#[derive(Debug)]
+pub enum LoadError {
+    /// A module's file cannot be read
+    Io { path: PathBuf, kind: io::ErrorKind },
+    /// A module's file cannot be parsed
+    Parse { path: PathBuf, error: ParseError },
+    /// Modules inline each other, listed from the first module of the cycle
+    /// back to itself
+    Cycle { paths: Vec<PathBuf> },
+    /// A module's file has the same name as a directory holding another
+    /// inlined module, as `geometry.lx` and `geometry/shapes.lx` do
+    Conflict { file: PathBuf, nested: PathBuf },
+}
+
+/// The names leading to a module from the root directory, as in
+/// `["geometry", "shapes"]` for `geometry/shapes.lx`
+type ModulePath = Vec<Rc<str>>;
+
+/// A parsed module along with the modules it inlines
+struct Module<'a> {
+    path: ModulePath,
+    expr: Node<Expr<'a, Loc>, Loc>,
+    dependencies: Vec<ModulePath>,
+}
+
+/// Loads modules from the files under a root directory, where inlining
+/// `..vector3` refers to `vector3.lx` and `..geometry.shapes` refers to
+/// `geometry/shapes.lx`
+///
+/// Each file is parsed once, no matter how many modules inline it.
+pub struct Loader<'a> {
+    root: PathBuf,
+    /// The path of each loaded file, indexed by its [FileId]
+    files: Vec<PathBuf>,
+    /// The parsed modules
+    modules: Vec<Module<'a>>,
+    /// The modules being loaded, innermost last
+    stack: Vec<ModulePath>,
+}
+
+impl<'a> Loader<'a> {
+    /// Constructs a loader for the files under `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: Vec::new(),
+            modules: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Returns the path of a loaded file, relative to the root directory
+    pub fn path(&self, file: FileId) -> Option<&Path> {
+        self.files.get(file.0).map(PathBuf::as_path)
+    }
+
+    /// Returns the paths of the loaded files, relative to the root directory
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Loads the module named `name` along with every module it inlines,
+    /// directly or not, returning it as a struct
+    ///
+    /// The inlined modules are declared before its own fields, nested in a
+    /// struct for each directory, so inlining `..geometry.shapes` finds the
+    /// `shapes` field of the `geometry` field. Modules inlined by other
+    /// modules are declared in the same place, which is in scope for them.
+    ///
+    /// A directory's struct only holds the modules in it, so a module cannot
+    /// be inlined along with a file of the same name as its directory.
+    pub fn load(&mut self, name: &[&str]) -> Result<Node<Expr<'a, Loc>, Loc>, LoadError> {
+        let path: ModulePath = name.iter().map(|name| Rc::from(*name)).collect();
+        let module = self.load_module(&path)?;
+        let loc = *module.get_meta();
+        let mut linked = Vec::new();
+        self.link(&path, &mut linked);
+        for file in &linked {
+            if let Some(nested) = linked
+                .iter()
+                .find(|nested| nested.len() > file.len() && nested.starts_with(file))
+            {
+                return Err(LoadError::Conflict {
+                    file: Self::file(file),
+                    nested: Self::file(nested),
+                });
+            }
+        }
+        let mut fields = Vec::new();
+        for dependency in linked.iter().filter(|dependency| **dependency != path) {
+            declare(&mut fields, dependency, &self.module(dependency).expr, &loc);
+        }
+        let Expr::Struct(struct_) = &*module.get() else {
+            unreachable!("modules parse as structs");
+        };
+        fields.extend(struct_.fields.iter().cloned());
+        Ok(Expr::Struct(Struct { fields }).node(loc))
+    }
+
+    /// Returns a loaded module
+    fn module(&self, path: &ModulePath) -> &Module<'a> {
+        self.modules
+            .iter()
+            .find(|module| module.path == *path)
+            .expect("linked modules are loaded")
+    }
+
+    /// Appends a loaded module to `linked` after the modules it inlines,
+    /// unless it is already there
+    fn link(&self, path: &ModulePath, linked: &mut Vec<ModulePath>) {
+        if linked.contains(path) {
+            return;
+        }
+        for dependency in &self.module(path).dependencies {
+            self.link(dependency, linked);
+        }
+        linked.push(path.clone());
+    }
+
+    /// Returns the file a module is stored in, relative to the root directory
+    fn file(path: &[Rc<str>]) -> PathBuf {
+        let mut file: PathBuf = path.iter().map(|name| &**name).collect();
+        file.set_extension(EXTENSION);
+        file
+    }
+
+    /// Parses a module and the modules it inlines, unless it is already loaded
+    fn load_module(&mut self, path: &ModulePath) -> Result<Node<Expr<'a, Loc>, Loc>, LoadError> {
+        if let Some(module) = self.modules.iter().find(|module| module.path == *path) {
+            return Ok(module.expr.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|loading| loading == path) {
+            let paths = self.stack[start..]
+                .iter()
+                .chain([path])
+                .map(|path| Self::file(path))
+                .collect();
+            return Err(LoadError::Cycle { paths });
+        }
+        let file = Self::file(path);
+        let source =
+            std::fs::read_to_string(self.root.join(&file)).map_err(|error| LoadError::Io {
+                path: file.clone(),
+                kind: error.kind(),
+            })?;
+        let id = FileId(self.files.len());
+        self.files.push(file.clone());
+        let module = parse_module(&mut Lexer::with_file(&source, id))
+            .map_err(|error| LoadError::Parse { path: file, error })?;
+        let dependencies = self.dependencies(&module);
+        self.stack.push(path.clone());
+        let loaded = dependencies
+            .iter()
+            .try_for_each(|dependency| self.load_module(dependency).map(drop));
+        self.stack.pop();
+        loaded?;
+        self.modules.push(Module {
+            path: path.clone(),
+            expr: module.clone(),
+            dependencies,
+        });
+        Ok(module)
+    }
+
+    /// Returns the modules a module inlines, in order
+    ///
+    /// An inlined path such as `geometry.shapes.Square` refers to the module
+    /// stored in the file named by its longest prefix, unless the module
+    /// declares the first name of the path itself or no such file exists.
+    fn dependencies(&self, module: &Node<Expr<'a, Loc>, Loc>) -> Vec<ModulePath> {
+        let Expr::Struct(struct_) = &*module.get() else {
+            return Vec::new();
+        };
+        let mut dependencies = Vec::new();
+        for field in &struct_.fields {
+            let names = match &*field.get() {
+                Field::Inline(expr) => inlined_names(expr),
+                Field::PartialInline(partial) => inlined_names(&partial.expr.get()),
+                _ => None,
+            };
+            let Some(names) = names else {
+                continue;
+            };
+            if declares(&struct_.fields, &names[0]) {
+                continue;
+            }
+            let dependency = (1..=names.len())
+                .rev()
+                .map(|len| names[..len].to_vec())
+                .find(|path| self.root.join(Self::file(path)).is_file());
+            if let Some(dependency) = dependency
+                && !dependencies.contains(&dependency)
+            {
+                dependencies.push(dependency);
+            }
+        }
+        dependencies
+    }
+}
+
+/// Returns the names of an inlined path of fields such as `geometry.shapes`
+fn inlined_names<M: NodeMeta>(expr: &Expr<'_, M>) -> Option<ModulePath> {
+    match expr {
+        Expr::Ident(ident) if ident.nshadow == 0 => Some(vec![ident.name.clone()]),
+        Expr::Project(project) => {
+            let mut names = inlined_names(&project.expr.get())?;
+            names.push(project.field.name.clone());
+            Some(names)
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if a struct declares a field named `name`
+fn declares<M: NodeMeta>(fields: &[Node<Field<'_, M>, M>], name: &str) -> bool {
+    fields.iter().any(|field| match &*field.get() {
+        Field::Field(ident, _) => &*ident.name == name,
+        Field::Method(method) => &*method.name.name == name,
+        _ => false,
+    })
+}
+
+/// Declares a module among `fields`, nested in a struct for each directory on
+/// its path
+fn declare<'a>(
+    fields: &mut Vec<Node<Field<'a, Loc>, Loc>>,
+    path: &[Rc<str>],
+    module: &Node<Expr<'a, Loc>, Loc>,
+    loc: &Loc,
+) {
+    let name = Ident {
+        name: path[0].clone(),
+        is_type: false,
+        is_void: false,
+        nshadow: 0,
+    };
+    if path.len() == 1 {
+        fields.push(Field::Field(name, module.clone()).node(*loc));
+        return;
+    }
+    let existing = fields.iter().find_map(|field| match &*field.get() {
+        Field::Field(ident, dir) if ident.name == name.name => Some(dir.clone()),
+        _ => None,
+    });
+    let dir = match existing {
+        Some(dir) => dir,
+        None => {
+            let dir = Expr::Struct(Struct { fields: Vec::new() }).node(*loc);
+            fields.push(Field::Field(name, dir.clone()).node(*loc));
+            dir
+        }
+    };
+    if let Expr::Struct(dir) = &mut *dir.get_mut() {
+        declare(&mut dir.fields, &path[1..], module, loc);
+    }
+}
@@ -0,0 +1,90 @@
+use super::*;
+use crate::{
+    eval::{Value, call, eval_module},
+    ir::lower::lower_module,
+};
+
+/// Returns the path of a fixture directory
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn test_load_modules() {
+    let mut loader = Loader::new(fixture("modules"));
+    let module = loader.load(&["main"]).unwrap();
+    let files: Vec<_> = loader
+        .files()
+        .iter()
+        .map(|file| file.to_str().unwrap())
+        .collect();
+    assert_eq!(
+        files,
+        ["main.lx", "vector3.lx", "geometry/shapes.lx", "math.lx"]
+    );
+    assert_eq!(
+        loader.path(FileId(2)),
+        Some(Path::new("geometry/shapes.lx"))
+    );
+    let Expr::Struct(struct_) = &*module.get() else {
+        panic!("modules load as structs");
+    };
+    let names: Vec<_> = struct_
+        .fields
+        .iter()
+        .filter_map(|field| match &*field.get() {
+            Field::Field(ident, _) => Some(ident.name.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        &*names,
+        [
+            Rc::from("vector3"),
+            "math".into(),
+            "geometry".into(),
+            "main".into()
+        ]
+    );
+
+    loader.load(&["geometry", "shapes"]).unwrap();
+    assert_eq!(loader.files().len(), 4);
+}
+
+#[test]
+fn test_eval_loaded_modules() {
+    let mut loader = Loader::new(fixture("modules"));
+    let module = loader.load(&["main"]).unwrap();
+    let ir = lower_module(&module).unwrap_or_else(|e| panic!("lowering: {e:?}"));
+    let module = eval_module(&ir).unwrap_or_else(|e| panic!("evaluating: {e:?}"));
+    let main = module.get("main").unwrap();
+    assert_eq!(
+        call(&main, vec![], Loc::default()),
+        Ok(Value::Prim(Prim::I32(18)))
+    );
+}
+
+#[test]
+fn test_load_errors() {
+    let mut loader = Loader::new(fixture("cycle"));
+    let Err(LoadError::Cycle { paths }) = loader.load(&["main"]) else {
+        panic!("expected a cycle");
+    };
+    assert_eq!(paths, ["a.lx", "b.lx", "c.lx", "a.lx"].map(PathBuf::from));
+
+    let mut loader = Loader::new(fixture("modules"));
+    let Err(LoadError::Io { path, kind }) = loader.load(&["missing"]) else {
+        panic!("expected a missing file");
+    };
+    assert_eq!(path, PathBuf::from("missing.lx"));
+    assert_eq!(kind, io::ErrorKind::NotFound);
+
+    let mut loader = Loader::new(fixture("conflict"));
+    let Err(LoadError::Conflict { file, nested }) = loader.load(&["main"]) else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(file, PathBuf::from("geometry.lx"));
+    assert_eq!(nested, PathBuf::from("geometry/shapes.lx"));
+}
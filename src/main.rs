@@ -1,10 +1,12 @@
 pub mod ast;
 pub mod colorscheme;
-// pub mod eval;
+pub mod eval;
 pub mod ir;
-pub mod node;
-// pub mod subtype;
 pub mod lexer;
+pub mod loader;
+pub mod node;
+pub mod parser;
+pub mod subtype;
 
 use ast::helpers::*;
 // use ir::IntoIr;
@@ -12,6 +14,20 @@ use ast::helpers::*;
 use crate::ast::pretty_print::PrettyPrint;
 
 fn main() {
+    if let Some(path) = std::env::args().nth(1) {
+        let path = std::path::Path::new(&path);
+        let root = path.parent().unwrap_or(std::path::Path::new(""));
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        match loader::Loader::new(root).load(&[name]) {
+            Ok(ast) => println!("{}", ast.get().pretty_print_string()),
+            Err(e) => eprintln!("{}: {e:?}", path.display()),
+        }
+        return;
+    }
+
     let ast = estruct::<()>([
         field(
             tid("Vector3"),
@@ -71,6 +71,10 @@ impl<T: NodeElt, M: NodeMeta> Node<T, M> {
         self.elt.borrow_mut()
     }
 
+    pub fn get_meta(&self) -> Ref<'_, M> {
+        self.meta.borrow()
+    }
+
     pub fn as_weak(&self) -> NodeWeak<T, M> {
         NodeWeak {
             elt: Rc::downgrade(&self.elt),
@@ -1,34 +1,1142 @@
 #[cfg(test)]
 mod tests;
 
-use crate::{ast::*, lexer::*, node::*};
+use crate::{
+    ast::*,
+    lexer::{literal::*, *},
+    node::*,
+};
 
 #[derive(Debug)]
 pub enum ParseError {
-    ExpectFailed { expected: Token, found: Token },
+    ExpectFailed {
+        expected: Token,
+        found: Token,
+        loc: Loc,
+    },
+    Unexpected {
+        expected: &'static str,
+        found: Token,
+        loc: Loc,
+    },
+    InvalidLiteral(Vec<LiteralError>),
     OutOfTokens,
     UnrecognizedToken(UnrecognizedTokenError),
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct Loc {}
+/// The location of a [Node] in the source code, used as its metadata
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Loc {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Loc {
+    /// Constructs a new [Loc] covering the given [Span] of `file`
+    pub fn new(file: FileId, span: Span) -> Self {
+        Self {
+            file,
+            start: span.start,
+            end: span.end,
+        }
+    }
+
+    /// Returns the smallest [Loc] covering both `self` and `other`
+    pub fn join(self, other: Loc) -> Loc {
+        debug_assert_eq!(self.file, other.file);
+        Loc {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Resolves the line and column of this [Loc] within its file's `source`
+    pub fn span(&self, source: &str) -> Span {
+        Span::locate(source, self.start, self.end)
+    }
+}
 
 impl<'source> Lexer<'source> {
     fn expect(&mut self, expected: Token) -> Result<&'source str, ParseError> {
-        let found = self.next();
-        let found = match found {
-            None => return Err(ParseError::OutOfTokens),
-            Some(Err(e)) => return Err(ParseError::UnrecognizedToken(e)),
-            Some(Ok(found)) => found,
-        };
+        let found = self.next_token()?;
         if found != expected {
-            return Err(ParseError::ExpectFailed { expected, found });
+            return Err(ParseError::ExpectFailed {
+                expected,
+                found,
+                loc: self.loc(),
+            });
         }
         Ok(self.slice())
     }
+
+    /// Consumes the next [Token], failing on unrecognized tokens
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        match self.next() {
+            None => Err(ParseError::OutOfTokens),
+            Some(Err(e)) => Err(ParseError::UnrecognizedToken(e)),
+            Some(Ok((found, _))) => Ok(found),
+        }
+    }
+
+    /// Consumes the next [Token] if it is `token`
+    fn eat(&mut self, token: Token) -> bool {
+        if self.peek() == Some(token) {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    /// Consumes a `>` closing an angle list, which may be the first half of a
+    /// `>>` closing two
+    fn eat_rangle(&mut self) -> bool {
+        self.eat(Token::RAngle) || self.split_rshift()
+    }
+
+    /// Consumes any number of [Token::Newline]s, returning `true` if there
+    /// were any
+    fn skip_newlines(&mut self) -> bool {
+        let mut skipped = false;
+        while self.eat(Token::Newline) {
+            skipped = true;
+        }
+        skipped
+    }
+
+    /// Consumes any number of [Token::Newline]s and [Token::Comma]s, and
+    /// returns whether there were any separators and whether they included a
+    /// blank line
+    fn skip_separators(&mut self) -> (bool, bool) {
+        let mut separated = false;
+        let mut blank = false;
+        let mut newline_end = None;
+        loop {
+            match self.peek() {
+                Some(Token::Newline) => {
+                    self.next();
+                    let span = self.span();
+                    let gap = newline_end.map(|end| &self.source()[end..span.start]);
+                    if self.slice().matches('\n').count() > 1
+                        || gap.is_some_and(|gap| gap.trim().is_empty())
+                    {
+                        blank = true;
+                    }
+                    newline_end = Some(span.end);
+                }
+                Some(Token::Comma) => {
+                    self.next();
+                    newline_end = None;
+                }
+                _ => break,
+            }
+            separated = true;
+        }
+        (separated, blank)
+    }
+
+    /// Returns `true` if the next token directly follows the most recently
+    /// consumed one, without any whitespace in between
+    fn peek_adjacent(&self) -> bool {
+        self.peek_span()
+            .is_some_and(|span| span.start == self.span().end)
+    }
+
+    /// Consumes a balanced group of parentheses, which must come next
+    fn skip_parens(&mut self) {
+        let mut depth = 0;
+        while let Some(Ok((token, _))) = self.next() {
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Builds the error for an unexpected next token
+    fn unexpected(&mut self, expected: &'static str) -> ParseError {
+        match self.next_token() {
+            Ok(found) => ParseError::Unexpected {
+                expected,
+                found,
+                loc: self.loc(),
+            },
+            Err(e) => e,
+        }
+    }
+
+    /// Returns the [Loc] of the most recently consumed token
+    fn loc(&self) -> Loc {
+        Loc::new(self.file(), self.span())
+    }
+}
+
+/// Returns the [Loc] stored in a node's metadata
+fn loc<T: NodeElt>(node: &Node<T, Loc>) -> Loc {
+    *node.get_meta()
+}
+
+/// Constructs an [Ident], which names a type if it starts with an uppercase
+/// letter
+pub fn ident(name: &str) -> Ident {
+    Ident {
+        name: name.into(),
+        is_type: name.starts_with(|c: char| c.is_ascii_uppercase()),
+        is_void: name == "_",
+        nshadow: 0,
+    }
+}
+
+/// Returns the binary operator a [Token] stands for
+fn binop(token: Token) -> Option<BinopKind> {
+    match token {
+        Token::PlusPlus => Some(BinopKind::Concat),
+        Token::Plus => Some(BinopKind::Add),
+        Token::Minus => Some(BinopKind::Sub),
+        Token::Times => Some(BinopKind::Mul),
+        Token::Divide => Some(BinopKind::Div),
+        Token::Caret => Some(BinopKind::Pow),
+        Token::EqualsEquals => Some(BinopKind::Eq),
+        Token::NotEquals => Some(BinopKind::Ne),
+        Token::LAngle => Some(BinopKind::Lt),
+        Token::LessEquals => Some(BinopKind::Le),
+        Token::RAngle => Some(BinopKind::Gt),
+        Token::GreaterEquals => Some(BinopKind::Ge),
+        _ => None,
+    }
+}
+
+/// Returns the operator named by the identifier about to be consumed, if it is
+/// an operator identifier such as `(+)`
+fn operator_ident(lexer: &Lexer<'_>) -> Option<BinopKind> {
+    let mut ahead = lexer.clone();
+    match (ahead.next(), ahead.next(), ahead.next()) {
+        (Some(Ok((Token::LParen, _))), Some(Ok((op, _))), Some(Ok((Token::RParen, _)))) => {
+            binop(op)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the operator of an updating assignment such as `+=`
+fn update_op(token: Token) -> Option<BinopKind> {
+    match token {
+        Token::PlusPlusEquals => Some(BinopKind::Concat),
+        Token::PlusEquals => Some(BinopKind::Add),
+        Token::MinusEquals => Some(BinopKind::Sub),
+        Token::TimesEquals => Some(BinopKind::Mul),
+        Token::DivideEquals => Some(BinopKind::Div),
+        Token::CaretEquals => Some(BinopKind::Pow),
+        _ => None,
+    }
+}
+
+/// Returns `true` if a [Token] can start an expression that directly follows
+/// a name, as in a field or a typed binding
+///
+/// Operators are excluded, since `x -y` is a subtraction.
+fn starts_expr(token: Token) -> bool {
+    matches!(
+        token,
+        Token::Number
+            | Token::String
+            | Token::Character
+            | Token::Ident
+            | Token::LParen
+            | Token::LCurl
+            | Token::LSquare
+            | Token::Dots
+    )
+}
+
+/// Returns `true` if the `(` about to be consumed opens a struct rather than a
+/// parenthesized expression
+///
+/// A struct is empty or starts with an inline field, a function definition or
+/// a name followed by its value. A function declared without a body only opens
+/// a struct if it ends its line, as in `(\n\tto_string()\n)`, since
+/// `(to_string())` is a parenthesized call.
+fn opens_struct(lexer: &Lexer<'_>) -> bool {
+    let mut lexer = lexer.clone();
+    lexer.next();
+    lexer.skip_newlines();
+    let first = match operator_ident(&lexer) {
+        Some(_) => {
+            lexer.skip_parens();
+            Some(Token::Ident)
+        }
+        None => lexer.next().and_then(Result::ok).map(|(token, _)| token),
+    };
+    match first {
+        Some(Token::RParen | Token::Dots | Token::DocComment) => true,
+        Some(Token::Ident) => match lexer.peek() {
+            Some(Token::LParen | Token::Bang) if lexer.peek_adjacent() => {
+                lexer.eat(Token::Bang);
+                lexer.skip_parens();
+                lexer
+                    .peek()
+                    .is_some_and(|token| token == Token::Newline || starts_expr(token))
+            }
+            Some(token) => starts_expr(token),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns `true` if the `(` about to be consumed opens the parameters of a
+/// lambda such as `(a, b) a + b`
+///
+/// The parameters are followed by the body on the same line, which cannot
+/// directly follow a struct or a parenthesized expression.
+fn opens_lambda(lexer: &Lexer<'_>) -> bool {
+    let mut lexer = lexer.clone();
+    lexer.skip_parens();
+    !lexer.peek_adjacent() && lexer.peek().is_some_and(starts_expr)
+}
+
+/// Returns `true` if the `{` about to be consumed opens the clauses of a
+/// function defined by matching rather than a block
+///
+/// The first clause starts with parameters followed by its body, as in a
+/// lambda.
+fn opens_match(lexer: &Lexer<'_>) -> bool {
+    let mut lexer = lexer.clone();
+    lexer.next();
+    lexer.skip_newlines();
+    lexer.peek() == Some(Token::LParen) && opens_lambda(&lexer)
+}
+
+/// Returns `true` if the `.` about to be consumed is followed by the list of
+/// fields a partial inline selects, as in `..std.(to_string)`
+fn opens_inline_names(lexer: &Lexer<'_>) -> bool {
+    let mut lexer = lexer.clone();
+    lexer.next();
+    lexer.peek() == Some(Token::LParen)
+}
+
+/// Parses the list of generic arguments the `<` about to be consumed opens,
+/// consuming nothing if it opens an enum instead
+///
+/// Generic arguments directly follow what they instantiate, as in `Dyn<T>`,
+/// and must be closed by a `>`. The list is parsed once, so nested lists are
+/// not parsed again for each level of lookahead.
+fn parse_type_args_ahead<'a>(lexer: &mut Lexer<'_>) -> Option<Vec<Node<Expr<'a, Loc>, Loc>>> {
+    if lexer.peek() != Some(Token::LAngle) || !lexer.peek_adjacent() {
+        return None;
+    }
+    let mut ahead = lexer.clone();
+    ahead.next();
+    let args = parse_type_args(&mut ahead).ok()?;
+    *lexer = ahead;
+    Some(args)
+}
+
+/// Parses a whole source file, which is an implicit struct whose fields are
+/// not wrapped in parentheses
+pub fn parse_module<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    let fields = parse_fields(lexer, None)?;
+    let loc = Loc {
+        file: lexer.file(),
+        start: 0,
+        end: lexer.source().len(),
+    };
+    Ok(Expr::Struct(Struct { fields }).node(loc))
+}
+
+/// Parses struct fields or enum variants up to and including `close`, or up to the end of the
+/// source code if there is none
+///
+/// Fields are separated by commas or newlines, and blank lines between them
+/// are kept as [Field::Spacer]s. Doc comments are kept as a [Field::Doc]
+/// before the field they document.
+fn parse_fields<'a>(
+    lexer: &mut Lexer<'_>,
+    close: Option<Token>,
+) -> Result<Vec<Node<Field<'a, Loc>, Loc>>, ParseError> {
+    let mut fields = Vec::new();
+    loop {
+        let (_, blank) = lexer.skip_separators();
+        let done = match close {
+            Some(close) => lexer.eat(close),
+            None => lexer.peek_span().is_none(),
+        };
+        if done {
+            break;
+        }
+        if blank && !fields.is_empty() {
+            fields.push(Field::Spacer.node(lexer.loc()));
+        }
+        if lexer.peek() == Some(Token::DocComment) {
+            fields.push(parse_doc(lexer, close)?);
+        }
+        fields.push(parse_field(lexer, close)?);
+    }
+    Ok(fields)
+}
+
+/// Parses the consecutive `---` doc comments before a field, which must
+/// follow them
+fn parse_doc<'a>(
+    lexer: &mut Lexer<'_>,
+    close: Option<Token>,
+) -> Result<Node<Field<'a, Loc>, Loc>, ParseError> {
+    let mut lines = Vec::new();
+    let mut start = None;
+    while lexer.eat(Token::DocComment) {
+        let line = &lexer.slice()["---".len()..];
+        lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end());
+        let loc = lexer.loc();
+        start = Some(start.map_or(loc, |start: Loc| start.join(loc)));
+        lexer.skip_newlines();
+    }
+    let loc = start.expect("doc comments come next");
+    if lexer.peek().is_none() || lexer.peek() == close {
+        return Err(ParseError::Unexpected {
+            expected: "a field after the doc comment",
+            found: Token::DocComment,
+            loc,
+        });
+    }
+    Ok(Field::Doc(lines.join("\n").into()).node(loc))
+}
+
+/// Parses a struct field, which is one of
+///
+/// - `..expr`, inlining the fields of `expr`
+/// - `..expr.(names)`, inlining only the named fields of `expr`
+/// - `name(params) body`, defining a function, or `name!(params) body`,
+///   explicitly overriding an earlier definition
+/// - `name { (params) body, ... }`, defining a function by clauses tried in
+///   order
+/// - `name<params> expr`, defining something generic, such as `Dyn<T> (...)`
+/// - `Type:name(params) body`, defining a method, whose receiver is mutable if
+///   prefixed by `*`
+/// - `name expr`
+/// - `Name` in an enum, short for the variant `Name ^Name`, as in
+///   `Expr <Ident, Prim <I32, String>>`
+///
+/// Fields closed by `>` are enum variants, whose values cannot contain
+/// comparisons.
+fn parse_field<'a>(
+    lexer: &mut Lexer<'_>,
+    close: Option<Token>,
+) -> Result<Node<Field<'a, Loc>, Loc>, ParseError> {
+    let parse_value = match close {
+        Some(Token::RAngle) => parse_angled_expr,
+        _ => parse_expr,
+    };
+    if lexer.eat(Token::Dots) {
+        let start = lexer.loc();
+        let expr = parse_value(lexer)?;
+        if lexer.eat(Token::Dot) {
+            let names = parse_inline_names(lexer)?;
+            let loc = start.join(lexer.loc());
+            return Ok(Field::PartialInline(PartialInline { expr, names }).node(loc));
+        }
+        let loc = start.join(loc(&expr));
+        return Ok(Field::Inline(expr.get().clone()).node(loc));
+    }
+    let is_mut = lexer.eat(Token::Times);
+    let star = lexer.loc();
+    let (name, name_loc) = parse_name(lexer)?;
+    let start = if is_mut { star } else { name_loc };
+    if is_mut || lexer.peek() == Some(Token::Colon) {
+        lexer.expect(Token::Colon)?;
+        let receiver = name;
+        let (name, _) = parse_name(lexer)?;
+        let (func, func_loc) = parse_func(lexer)?;
+        let method = Method {
+            receiver,
+            is_mut,
+            name,
+            func,
+        };
+        return Ok(Field::Method(method).node(start.join(func_loc)));
+    }
+    let generic = match lexer.peek() {
+        Some(Token::LAngle) if lexer.peek_adjacent() => {
+            lexer.next();
+            let start = lexer.loc();
+            let params = parse_generic_params(lexer)?;
+            Some((params, start.join(lexer.loc())))
+        }
+        _ => None,
+    };
+    let value = match lexer.peek() {
+        Some(Token::LParen | Token::Bang) if lexer.peek_adjacent() => {
+            let (func, loc) = parse_func(lexer)?;
+            Expr::Func(func).node(loc)
+        }
+        Some(Token::LCurl) if opens_match(lexer) => parse_match(lexer)?,
+        Some(Token::Comma | Token::Newline | Token::RAngle) if close == Some(Token::RAngle) => {
+            Expr::Ident(Ident {
+                nshadow: 1,
+                ..name.clone()
+            })
+            .node(start)
+        }
+        _ => parse_value(lexer)?,
+    };
+    let value = match generic {
+        Some((params, params_loc)) => {
+            let loc = params_loc.join(loc(&value));
+            Expr::Generic(Generic {
+                params,
+                expr: value,
+            })
+            .node(loc)
+        }
+        None => value,
+    };
+    let loc = start.join(loc(&value));
+    Ok(Field::Field(name, value).node(loc))
+}
+
+/// Parses the fields a partial inline selects after its `.`, separated by
+/// commas or newlines, each of which may be preceded by an alias, as in
+/// `(show to_string, print)`
+fn parse_inline_names(lexer: &mut Lexer<'_>) -> Result<Vec<Node<InlineName, Loc>>, ParseError> {
+    lexer.expect(Token::LParen)?;
+    let mut names = Vec::new();
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        let (name, start) = parse_name(lexer)?;
+        let name = match lexer.peek() {
+            Some(Token::Ident | Token::LParen) => {
+                let (original, end) = parse_name(lexer)?;
+                InlineName {
+                    alias: Some(name),
+                    name: original,
+                }
+                .node(start.join(end))
+            }
+            _ => InlineName { alias: None, name }.node(start),
+        };
+        names.push(name);
+        let separated = lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `)`"));
+        }
+    }
+    Ok(names)
+}
+
+/// Parses a list of generic parameters such as `<T, Self Vector3>` after its
+/// `<`, where each may be followed by the value it is fixed to
+fn parse_generic_params<'a>(
+    lexer: &mut Lexer<'_>,
+) -> Result<Vec<Node<GenericParam<'a, Loc>, Loc>>, ParseError> {
+    let mut params = Vec::new();
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat_rangle() {
+            break;
+        }
+        let (ident, ident_loc) = parse_ident(lexer)?;
+        let param = match lexer.peek() {
+            Some(token) if starts_expr(token) => {
+                let value = parse_angled_expr(lexer)?;
+                let loc = ident_loc.join(loc(&value));
+                GenericParam {
+                    ident,
+                    value: Some(value),
+                }
+                .node(loc)
+            }
+            _ => GenericParam { ident, value: None }.node(ident_loc),
+        };
+        params.push(param);
+        let separated = lexer.skip_newlines();
+        if lexer.eat_rangle() {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `>`"));
+        }
+    }
+    Ok(params)
+}
+
+/// Parses a list of generic arguments such as `<I32, String>` after its `<`
+fn parse_type_args<'a>(lexer: &mut Lexer<'_>) -> Result<Vec<Node<Expr<'a, Loc>, Loc>>, ParseError> {
+    let mut args = Vec::new();
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat_rangle() {
+            break;
+        }
+        args.push(parse_angled_expr(lexer)?);
+        let separated = lexer.skip_newlines();
+        if lexer.eat_rangle() {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `>`"));
+        }
+    }
+    Ok(args)
+}
+
+/// Parses the parameters and body of a function definition, which overrides
+/// an earlier definition if its parameters are directly preceded by `!`
+///
+/// A function only declared, as in `to_string()`, has the body `()`.
+fn parse_func<'a>(lexer: &mut Lexer<'_>) -> Result<(Func<'a, Loc>, Loc), ParseError> {
+    let is_override = lexer.peek() == Some(Token::Bang) && lexer.peek_adjacent();
+    if is_override {
+        lexer.next();
+        if !lexer.peek_adjacent() {
+            return Err(lexer.unexpected("`(`"));
+        }
+    }
+    let params = parse_params(lexer)?;
+    let body = match lexer.peek() {
+        Some(Token::Newline | Token::Comma | Token::RParen | Token::RCurl | Token::RAngle)
+        | None => Expr::Struct(Struct { fields: Vec::new() }).node(lexer.loc()),
+        _ => parse_expr(lexer)?,
+    };
+    let loc = loc(&params).join(loc(&body));
+    Ok((
+        Func {
+            params,
+            body,
+            is_override,
+        },
+        loc,
+    ))
+}
+
+/// Parses the clauses of a function defined by matching, as in
+/// `add { (2, 2) 5, (x I32, y I32) x + y }`, separated by commas or newlines
+fn parse_match<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::LCurl)?;
+    let start = lexer.loc();
+    let mut clauses = Vec::new();
+    let (mut separated, _) = lexer.skip_separators();
+    while !lexer.eat(Token::RCurl) {
+        if !clauses.is_empty() && !separated {
+            return Err(lexer.unexpected("`,` or `}`"));
+        }
+        let (func, loc) = parse_func(lexer)?;
+        clauses.push(Expr::Func(func).node(loc));
+        (separated, _) = lexer.skip_separators();
+    }
+    Ok(Expr::Match(Match { clauses }).node(start.join(lexer.loc())))
+}
+
+/// Parses a parenthesized list of parameters, separated by commas or newlines
+pub fn parse_params<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Params<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::LParen)?;
+    let start = lexer.loc();
+    let mut params = Vec::new();
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        params.push(parse_param(lexer)?);
+        let separated = lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `)`"));
+        }
+    }
+    Ok(Params { params }.node(start.join(lexer.loc())))
+}
+
+/// Parses a parameter and its type, which is passed mutably if prefixed by
+/// `*` and may be followed by `= default`
+///
+/// The type may be omitted, as in the lambda `(a, b) a + b`. A literal such as
+/// the `0` in `fibonacci(0) 0` is an unnamed parameter whose type is the
+/// literal itself.
+fn parse_param<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Param<'a, Loc>, Loc>, ParseError> {
+    if let Some(Token::Number | Token::String | Token::Character) = lexer.peek() {
+        let expr = parse_primary(lexer)?;
+        let loc = loc(&expr);
+        return Ok(Param {
+            ident: ident("_"),
+            expr: Some(expr),
+            is_mut: false,
+            default: None,
+        }
+        .node(loc));
+    }
+    let is_mut = lexer.eat(Token::Times);
+    let star = lexer.loc();
+    let ident = ident(lexer.expect(Token::Ident)?);
+    let start = if is_mut { star } else { lexer.loc() };
+    let expr = match lexer.peek() {
+        Some(Token::Comma | Token::Newline | Token::RParen | Token::Equals) => None,
+        _ => Some(parse_expr(lexer)?),
+    };
+    let default = match lexer.eat(Token::Equals) {
+        true => Some(parse_expr(lexer)?),
+        false => None,
+    };
+    let loc = match default.as_ref().or(expr.as_ref()) {
+        Some(end) => start.join(loc(end)),
+        None => start,
+    };
+    Ok(Param {
+        ident,
+        expr,
+        is_mut,
+        default,
+    }
+    .node(loc))
+}
+
+/// Parses a block of statements, separated by commas or newlines
+///
+/// Blank lines between statements are kept as [Stmt::Spacer]s.
+fn parse_block<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::LCurl)?;
+    let start = lexer.loc();
+    let mut stmts = Vec::new();
+    let (mut separated, mut blank) = lexer.skip_separators();
+    while !lexer.eat(Token::RCurl) {
+        if !stmts.is_empty() {
+            if !separated {
+                return Err(lexer.unexpected("`,` or `}`"));
+            }
+            if blank {
+                stmts.push(Stmt::Spacer.node(lexer.loc()));
+            }
+        }
+        stmts.push(parse_stmt(lexer)?);
+        (separated, blank) = lexer.skip_separators();
+    }
+    Ok(Expr::Block(Block { stmts }).node(start.join(lexer.loc())))
+}
+
+/// Parses a statement, which is one of
+///
+/// - `name = expr`, binding a name
+/// - `name Type = expr`, binding a mutable name
+/// - `target := expr`, writing to a mutable place
+/// - `target op= expr`, updating a mutable place
+/// - `=> expr`, returning from the enclosing function
+/// - `expr`
+fn parse_stmt<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Stmt<'a, Loc>, Loc>, ParseError> {
+    if lexer.eat(Token::FatArrow) {
+        let start = lexer.loc();
+        let value = parse_expr(lexer)?;
+        let loc = start.join(loc(&value));
+        return Ok(Stmt::Return(value).node(loc));
+    }
+    if lexer.peek() == Some(Token::Ident) {
+        let mut ahead = lexer.clone();
+        ahead.next();
+        let binds = match ahead.peek() {
+            Some(Token::Equals) => true,
+            Some(Token::LParen) => !ahead.peek_adjacent(),
+            Some(token) => starts_expr(token),
+            None => false,
+        };
+        if binds {
+            return parse_bind(lexer);
+        }
+    }
+    let target = parse_expr(lexer)?;
+    let start = loc(&target);
+    let stmt = if lexer.eat(Token::ColonEquals) {
+        let value = parse_expr(lexer)?;
+        Stmt::Write(Write { target, value })
+    } else if let Some(op) = lexer.peek().and_then(update_op) {
+        lexer.next();
+        let value = parse_expr(lexer)?;
+        Stmt::Update(Update { target, op, value })
+    } else {
+        return Ok(Stmt::Expr(target).node(start));
+    };
+    Ok(stmt.node(start.join(lexer.loc())))
+}
+
+/// Parses a binding, which is mutable if its name is followed by a type
+fn parse_bind<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Stmt<'a, Loc>, Loc>, ParseError> {
+    let name = ident(lexer.expect(Token::Ident)?);
+    let start = lexer.loc();
+    let stmt = if lexer.eat(Token::Equals) {
+        let value = parse_expr(lexer)?;
+        Stmt::Bind(Bind { name, value })
+    } else {
+        let initial = parse_expr(lexer)?;
+        lexer.expect(Token::Equals)?;
+        let update = parse_expr(lexer)?;
+        Stmt::BindMut(BindMut {
+            name,
+            initial,
+            update,
+        })
+    };
+    Ok(stmt.node(start.join(lexer.loc())))
 }
 
 pub fn parse_number<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
     let slice = lexer.expect(Token::Number)?;
-    panic!("{slice}");
+    let number =
+        decode_number(slice, lexer.span()).map_err(|e| ParseError::InvalidLiteral(vec![e]))?;
+    Ok(Expr::Prim(number.into()).node(lexer.loc()))
+}
+
+/// Parses a string literal, which is a [Template] if it interpolates any
+/// expressions
+pub fn parse_string<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::String)?;
+    let loc = lexer.loc();
+    let mut parts = Vec::new();
+    for segment in lexer.segments().map_err(ParseError::InvalidLiteral)? {
+        parts.push(match segment {
+            Segment::Text(text) => TemplatePart::Text(text),
+            Segment::Expr(mut lexer) => {
+                let expr = parse_expr(&mut lexer)?;
+                if lexer.peek().is_some() {
+                    return Err(lexer.unexpected("end of interpolation"));
+                }
+                TemplatePart::Expr(expr)
+            }
+        });
+    }
+    let expr = match parts.as_slice() {
+        [] => Expr::Prim(Prim::String(String::new())),
+        [TemplatePart::Text(text)] => Expr::Prim(Prim::String(text.clone())),
+        _ => Expr::Template(Template { parts }),
+    };
+    Ok(expr.node(loc))
+}
+
+pub fn parse_char<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    let slice = lexer.expect(Token::Character)?;
+    let c = decode_char(slice, lexer.span()).map_err(ParseError::InvalidLiteral)?;
+    Ok(Expr::Prim(Prim::Char(c)).node(lexer.loc()))
+}
+
+/// Parses an identifier, possibly prefixed by `^`s that refer past shadowing
+/// declarations
+pub fn parse_ident(lexer: &mut Lexer<'_>) -> Result<(Ident, Loc), ParseError> {
+    let mut nshadow = 0;
+    let mut start = None;
+    while lexer.eat(Token::Caret) {
+        nshadow += 1;
+        start.get_or_insert(lexer.loc());
+    }
+    let (name, loc) = parse_name(lexer)?;
+    Ok((
+        Ident { nshadow, ..name },
+        start.map_or(loc, |start| start.join(loc)),
+    ))
+}
+
+/// Parses a name, which is an identifier or an operator identifier such as
+/// `(+)`
+fn parse_name(lexer: &mut Lexer<'_>) -> Result<(Ident, Loc), ParseError> {
+    match operator_ident(lexer) {
+        Some(op) => {
+            lexer.next();
+            let start = lexer.loc();
+            lexer.next();
+            lexer.next();
+            Ok((ident(op.ident_name()), start.join(lexer.loc())))
+        }
+        None => {
+            let name = lexer.expect(Token::Ident)?;
+            Ok((ident(name), lexer.loc()))
+        }
+    }
+}
+
+/// Parses an expression
+pub fn parse_expr<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    parse_binop(lexer, 0)
+}
+
+/// Parses an expression between angle brackets, where `>` closes the brackets
+/// rather than comparing
+fn parse_angled_expr<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    parse_binop(lexer, BinopKind::Concat.precedence())
+}
+
+/// Parses a chain of binary operators binding at least as tightly as
+/// `min_prec`
+fn parse_binop<'a>(
+    lexer: &mut Lexer<'_>,
+    min_prec: u8,
+) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    let mut lhs = parse_unop(lexer)?;
+    while let Some(op) = lexer.peek().and_then(binop) {
+        if op.precedence() < min_prec {
+            break;
+        }
+        lexer.next();
+        let rhs = match op.is_right_assoc() {
+            true => parse_binop(lexer, op.precedence())?,
+            false => parse_binop(lexer, op.precedence() + 1)?,
+        };
+        let loc = loc(&lhs).join(loc(&rhs));
+        lhs = Expr::Binop(Binop { lhs, op, rhs }).node(loc);
+    }
+    Ok(lhs)
+}
+
+/// Parses a prefix operator, whose operand may only contain operators that
+/// bind tighter than it
+fn parse_unop<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    let op = match lexer.peek() {
+        Some(Token::Minus) => UnopKind::Neg,
+        Some(Token::Plus) => UnopKind::Copy,
+        Some(Token::Times) => {
+            lexer.next();
+            let start = lexer.loc();
+            let expr = parse_postfix(lexer, true)?;
+            let loc = start.join(loc(&expr));
+            return Ok(expr.mmap(|_| loc));
+        }
+        _ => return parse_postfix(lexer, false),
+    };
+    lexer.next();
+    let start = lexer.loc();
+    let expr = parse_binop(lexer, BinopKind::Pow.precedence())?;
+    let loc = start.join(loc(&expr));
+    Ok(Expr::Unop(Unop { op, expr }).node(loc))
+}
+
+/// Parses an expression followed by any number of calls, method calls,
+/// projections and generic arguments
+///
+/// The parentheses of a method call without arguments may be omitted, as in
+/// `x:m`. If `mut_receiver` is set, the expression must be the receiver of a
+/// method call, which it is passed to mutably.
+fn parse_postfix<'a>(
+    lexer: &mut Lexer<'_>,
+    mut mut_receiver: bool,
+) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    let mut expr = parse_primary(lexer)?;
+    loop {
+        let start = loc(&expr);
+        match lexer.peek() {
+            // `.(` selects fields to inline, as in `..std.(to_string)`
+            Some(Token::Dot) if !opens_inline_names(lexer) => {
+                lexer.next();
+                let (field, field_loc) = parse_ident(lexer)?;
+                expr = Expr::Project(Project { expr, field }).node(start.join(field_loc));
+            }
+            Some(Token::LAngle) => {
+                let Some(args) = parse_type_args_ahead(lexer) else {
+                    break;
+                };
+                expr = Expr::Instantiate(Instantiate { expr, args }).node(start.join(lexer.loc()));
+            }
+            Some(Token::LParen) => {
+                let args = parse_args(lexer)?;
+                let loc = start.join(loc(&args));
+                expr = Expr::Call(Call {
+                    func: expr,
+                    args,
+                    method_syntax: false,
+                })
+                .node(loc);
+            }
+            Some(Token::Colon) => {
+                lexer.next();
+                let (name, name_loc) = parse_ident(lexer)?;
+                let mut func = Expr::Ident(name).node(name_loc);
+                if let Some(args) = parse_type_args_ahead(lexer) {
+                    func = Expr::Instantiate(Instantiate { expr: func, args })
+                        .node(name_loc.join(lexer.loc()));
+                }
+                let receiver = Arg {
+                    name: None,
+                    expr,
+                    is_mut: mut_receiver,
+                }
+                .node(start);
+                mut_receiver = false;
+                let args = match lexer.peek() {
+                    Some(Token::LParen) => parse_args(lexer)?,
+                    _ => Args { args: Vec::new() }.node(lexer.loc()),
+                };
+                let args_loc = loc(&args);
+                args.get_mut().args.insert(0, receiver);
+                let args = args.mmap(|_| start.join(args_loc));
+                expr = Expr::Call(Call {
+                    func,
+                    args,
+                    method_syntax: true,
+                })
+                .node(start.join(args_loc));
+            }
+            _ => break,
+        }
+    }
+    if mut_receiver {
+        return Err(lexer.unexpected("`:` after a mutable receiver"));
+    }
+    Ok(expr)
+}
+
+/// Parses a literal, an identifier, an operator identifier, a lambda, a struct, an enum, a constructor,
+/// a block, a conditional, a list, a list type or a parenthesized expression
+fn parse_primary<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    match lexer.peek() {
+        Some(Token::LParen) if operator_ident(lexer).is_some() => {
+            let (ident, loc) = parse_ident(lexer)?;
+            Ok(Expr::Ident(ident).node(loc))
+        }
+        Some(Token::LParen) if opens_lambda(lexer) => {
+            let (func, loc) = parse_func(lexer)?;
+            Ok(Expr::Func(func).node(loc))
+        }
+        Some(Token::LParen) if opens_struct(lexer) => {
+            lexer.next();
+            let start = lexer.loc();
+            let fields = parse_fields(lexer, Some(Token::RParen))?;
+            Ok(Expr::Struct(Struct { fields }).node(start.join(lexer.loc())))
+        }
+        Some(Token::LAngle) => {
+            lexer.next();
+            let start = lexer.loc();
+            let variants = parse_fields(lexer, Some(Token::RAngle))?;
+            Ok(Expr::Enum(Enum { variants }).node(start.join(lexer.loc())))
+        }
+        Some(Token::Dots) => {
+            lexer.next();
+            let start = lexer.loc();
+            let name = ident(lexer.expect(Token::Ident)?);
+            lexer.expect(Token::LParen)?;
+            let fields = Struct {
+                fields: parse_fields(lexer, Some(Token::RParen))?,
+            };
+            Ok(Expr::Constructor(Constructor { name, fields }).node(start.join(lexer.loc())))
+        }
+        Some(Token::LCurl) => parse_block(lexer),
+        Some(Token::LSquare) => parse_list(lexer),
+        Some(Token::QuestionMark) => {
+            lexer.next();
+            let start = lexer.loc();
+            lexer.expect(Token::LParen)?;
+            lexer.skip_newlines();
+            let cond = parse_expr(lexer)?;
+            lexer.skip_newlines();
+            lexer.expect(Token::RParen)?;
+            let then = parse_expr(lexer)?;
+            let loc = start.join(loc(&then));
+            Ok(Expr::If(If { cond, then }).node(loc))
+        }
+        Some(Token::Number) => parse_number(lexer),
+        Some(Token::String) => parse_string(lexer),
+        Some(Token::Character) => parse_char(lexer),
+        Some(Token::Ident | Token::Caret) => {
+            let (ident, loc) = parse_ident(lexer)?;
+            Ok(Expr::Ident(ident).node(loc))
+        }
+        Some(Token::LParen) => {
+            lexer.next();
+            let start = lexer.loc();
+            lexer.skip_newlines();
+            let expr = parse_expr(lexer)?;
+            lexer.skip_newlines();
+            lexer.expect(Token::RParen)?;
+            let loc = start.join(lexer.loc());
+            Ok(expr.mmap(|_| loc))
+        }
+        _ => Err(lexer.unexpected("expression")),
+    }
+}
+
+/// Parses a list literal, whose items are separated by commas or newlines, or
+/// a list type such as `[]I32`, whose item type directly follows the `[]`
+fn parse_list<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Expr<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::LSquare)?;
+    let start = lexer.loc();
+    let mut items = Vec::new();
+    if lexer.eat(Token::RSquare) {
+        if lexer.peek_adjacent() && lexer.peek().is_some_and(starts_expr) {
+            let item = parse_postfix(lexer, false)?;
+            let loc = start.join(loc(&item));
+            return Ok(Expr::ListType(ListType { item }).node(loc));
+        }
+        return Ok(Expr::List(List { items }).node(start.join(lexer.loc())));
+    }
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat(Token::RSquare) {
+            break;
+        }
+        items.push(parse_expr(lexer)?);
+        let separated = lexer.skip_newlines();
+        if lexer.eat(Token::RSquare) {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `]`"));
+        }
+    }
+    Ok(Expr::List(List { items }).node(start.join(lexer.loc())))
+}
+
+/// Parses a parenthesized list of arguments, separated by commas or newlines
+pub fn parse_args<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Args<'a, Loc>, Loc>, ParseError> {
+    lexer.expect(Token::LParen)?;
+    let start = lexer.loc();
+    let mut args = Vec::new();
+    loop {
+        lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        args.push(parse_arg(lexer)?);
+        let separated = lexer.skip_newlines();
+        if lexer.eat(Token::RParen) {
+            break;
+        }
+        if !lexer.eat(Token::Comma) && !separated {
+            return Err(lexer.unexpected("`,` or `)`"));
+        }
+    }
+    Ok(Args { args }.node(start.join(lexer.loc())))
+}
+
+/// Parses an argument, which is named if prefixed by `name=` and passed
+/// mutably if its value is prefixed by `*`
+fn parse_arg<'a>(lexer: &mut Lexer<'_>) -> Result<Node<Arg<'a, Loc>, Loc>, ParseError> {
+    let mut ahead = lexer.clone();
+    let name = match (ahead.next(), ahead.peek()) {
+        (Some(Ok((Token::Ident, _))), Some(Token::Equals)) => {
+            let name = ident(lexer.expect(Token::Ident)?);
+            let start = lexer.loc();
+            lexer.expect(Token::Equals)?;
+            Some((name, start))
+        }
+        _ => None,
+    };
+    let is_mut = lexer.eat(Token::Times);
+    let star = lexer.loc();
+    let expr = parse_expr(lexer)?;
+    let start = match (&name, is_mut) {
+        (Some((_, start)), _) => *start,
+        (None, true) => star,
+        (None, false) => loc(&expr),
+    };
+    let loc = start.join(loc(&expr));
+    Ok(Arg {
+        name: name.map(|(name, _)| name),
+        expr,
+        is_mut,
+    }
+    .node(loc))
 }
@@ -1,7 +1,37 @@
 use super::*;
+use crate::ast::{helpers::*, pretty_print::*};
 
-fn make_lexer(source: &str) -> Lexer {
-    Lexer::new(source).unwrap_or_else(|_| panic!("unable to construct lexer for source {source}"))
+fn make_lexer(source: &str) -> Lexer<'_> {
+    Lexer::new(source)
+}
+
+fn parse(source: &str) -> Node<Expr<'static, Loc>, Loc> {
+    let mut lexer = make_lexer(source);
+    let expr = parse_expr(&mut lexer).unwrap_or_else(|e| panic!("parsing {source}: {e:?}"));
+    assert_eq!(lexer.peek(), None, "unparsed tokens in {source}");
+    expr
+}
+
+fn print<M: NodeMeta>(expr: &Node<Expr<'_, M>, M>) -> String {
+    expr.get()
+        .pretty_print(&mut PrettyPrintContext::default().with_colors(false))
+}
+
+/// Parses each source and compares it with the tree built by [helpers],
+/// which must print identically
+fn assert_parses(cases: Vec<(&str, Node<Expr<'static, Loc>, Loc>)>) {
+    for (source, expected) in cases {
+        let actual = parse(source);
+        assert_eq!(print(&actual), print(&expected), "parsing {source}");
+    }
+}
+
+fn loc(start: usize, end: usize) -> Loc {
+    Loc {
+        file: FileId(0),
+        start,
+        end,
+    }
 }
 
 #[test]
@@ -9,3 +39,1000 @@ fn test_parse_number() {
     let mut lexer = make_lexer("42");
     parse_number(&mut lexer).unwrap();
 }
+
+#[test]
+fn test_parse_number_loc() {
+    let mut lexer = Lexer::with_file("  \n 1234", FileId(3));
+    let err = parse_number(&mut lexer).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::ExpectFailed {
+            expected: Token::Number,
+            found: Token::Newline,
+            loc: Loc {
+                file: FileId(3),
+                start: 2,
+                end: 3
+            }
+        }
+    ));
+
+    let node = parse_number(&mut lexer).unwrap();
+    assert_eq!(*node.get(), Expr::Prim(Prim::I32(1234)));
+    let loc = *node.get_meta();
+    assert_eq!(
+        loc,
+        Loc {
+            file: FileId(3),
+            start: 4,
+            end: 8
+        }
+    );
+    assert_eq!(loc.span(lexer.source()).line, 2);
+    assert_eq!(loc.span(lexer.source()).col, 2);
+}
+
+#[test]
+fn test_parse_literals() {
+    let cases = [
+        ("42", Prim::I32(42)),
+        ("0xffu8", Prim::U8(255)),
+        ("7i64", Prim::I64(7)),
+        ("1.5", Prim::F32(1.5)),
+        ("2.5f64", Prim::F64(2.5)),
+        (r#""hi\n""#, Prim::String("hi\n".to_owned())),
+        (r#""{{braces}}""#, Prim::String("{braces}".to_owned())),
+        (r#""""#, Prim::String(String::new())),
+        ("'\u{e9}'", Prim::Char('\u{e9}')),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(
+            *parse(source).get(),
+            Expr::Prim(expected),
+            "parsing {source}"
+        );
+    }
+    assert!(matches!(
+        parse_expr(&mut make_lexer("256u8")),
+        Err(ParseError::InvalidLiteral(_))
+    ));
+    assert!(matches!(
+        parse_expr(&mut make_lexer(r#""\q""#)),
+        Err(ParseError::InvalidLiteral(_))
+    ));
+}
+
+#[test]
+fn test_parse_idents() {
+    assert_eq!(*parse("foo").get(), Expr::Ident(vid("foo")));
+    assert_eq!(*parse("Foo").get(), Expr::Ident(tid("Foo")));
+    assert_eq!(*parse("_").get(), Expr::Ident(void()));
+    assert_eq!(*parse("^^x").get(), Expr::Ident(unshadow(vid("x"), 2)));
+    assert_eq!(*parse("^^x").get_meta(), loc(0, 3));
+}
+
+#[test]
+fn test_parse_precedence() {
+    assert_parses(vec![
+        ("1 + 2 * 3", add(ei32(1), mul(ei32(2), ei32(3)))),
+        ("(1 + 2) * 3", mul(add(ei32(1), ei32(2)), ei32(3))),
+        ("a - b - c", sub(sub(evid("a"), evid("b")), evid("c"))),
+        ("a - (b - c)", sub(evid("a"), sub(evid("b"), evid("c")))),
+        ("a / b * c", mul(div(evid("a"), evid("b")), evid("c"))),
+        ("a ^ b ^ c", pow(evid("a"), pow(evid("b"), evid("c")))),
+        ("(a ^ b) ^ c", pow(pow(evid("a"), evid("b")), evid("c"))),
+        ("-x ^ 2", neg(pow(evid("x"), ei32(2)))),
+        ("(-x) ^ 2", pow(neg(evid("x")), ei32(2))),
+        ("2 ^ -1", pow(ei32(2), neg(ei32(1)))),
+        ("-a * b", mul(neg(evid("a")), evid("b"))),
+        ("-(a * b)", neg(mul(evid("a"), evid("b")))),
+        ("+x", copy(evid("x"))),
+        ("a ++ b + c", concat(evid("a"), add(evid("b"), evid("c")))),
+        ("(a ++ b) + c", add(concat(evid("a"), evid("b")), evid("c"))),
+        (
+            "a ++ b ++ c",
+            concat(concat(evid("a"), evid("b")), evid("c")),
+        ),
+        (
+            "self.x^2 + self.y^2 + self.z^2",
+            add(
+                add(
+                    pow(eproj(evid("self"), vid("x")), ei32(2)),
+                    pow(eproj(evid("self"), vid("y")), ei32(2)),
+                ),
+                pow(eproj(evid("self"), vid("z")), ei32(2)),
+            ),
+        ),
+    ]);
+}
+
+#[test]
+fn test_parse_postfix() {
+    assert_parses(vec![
+        ("x.y.z", eproj(eproj(evid("x"), vid("y")), vid("z"))),
+        ("Cow.Owned", eproj(etid("Cow"), tid("Owned"))),
+        ("f()", ecall(evid("f"), args([]))),
+        (
+            "f(a, b)",
+            ecall(evid("f"), args([arg(evid("a")), arg(evid("b"))])),
+        ),
+        (
+            "f(\n    a\n    b,\n)",
+            ecall(evid("f"), args([arg(evid("a")), arg(evid("b"))])),
+        ),
+        (
+            "f(*a, b)",
+            ecall(evid("f"), args([arg_mut(evid("a")), arg(evid("b"))])),
+        ),
+        (
+            "f(x)(y)",
+            ecall(
+                ecall(evid("f"), args([arg(evid("x"))])),
+                args([arg(evid("y"))]),
+            ),
+        ),
+        (
+            "Vector3.add(vec_a, vec_b)",
+            ecall(
+                eproj(etid("Vector3"), vid("add")),
+                args([arg(evid("vec_a")), arg(evid("vec_b"))]),
+            ),
+        ),
+        (
+            "Cow.Owned(\"Hello, world!\")",
+            ecall(
+                eproj(etid("Cow"), tid("Owned")),
+                args([arg(estring("Hello, world!"))]),
+            ),
+        ),
+        (
+            "self:len_sq():sqrt()",
+            emethod(
+                evid("sqrt"),
+                args([arg(emethod(evid("len_sq"), args([arg(evid("self"))])))]),
+            ),
+        ),
+        (
+            "io:println(vec_c:to_string())",
+            emethod(
+                evid("println"),
+                args([
+                    arg(evid("io")),
+                    arg(emethod(evid("to_string"), args([arg(evid("vec_c"))]))),
+                ]),
+            ),
+        ),
+        (
+            "*vec_c:normalize()",
+            emethod(evid("normalize"), args([arg_mut(evid("vec_c"))])),
+        ),
+        (
+            "(a + b):sqrt()",
+            emethod(evid("sqrt"), args([arg(add(evid("a"), evid("b")))])),
+        ),
+        ("(-v).x", eproj(neg(evid("v")), vid("x"))),
+        ("-v.x", neg(eproj(evid("v"), vid("x")))),
+    ]);
+}
+
+#[test]
+fn test_parse_templates() {
+    assert_parses(vec![
+        (
+            r#""({x}, {y}, {z})""#,
+            etemplate([
+                ttext("("),
+                texpr(evid("x")),
+                ttext(", "),
+                texpr(evid("y")),
+                ttext(", "),
+                texpr(evid("z")),
+                ttext(")"),
+            ]),
+        ),
+        (
+            r#""{self.name} (age {self.age + 1})""#,
+            etemplate([
+                texpr(eproj(evid("self"), vid("name"))),
+                ttext(" (age "),
+                texpr(add(eproj(evid("self"), vid("age")), ei32(1))),
+                ttext(")"),
+            ]),
+        ),
+    ]);
+    let template = parse(r#""a{x}""#);
+    let Expr::Template(Template { parts }) = &*template.get() else {
+        panic!("expected a template");
+    };
+    let TemplatePart::Expr(x) = &parts[1] else {
+        panic!("expected an interpolated expression");
+    };
+    assert_eq!(*x.get_meta(), loc(3, 4));
+    assert!(parse_expr(&mut make_lexer(r#""{1 +}""#)).is_err());
+    assert!(parse_expr(&mut make_lexer(r#""{1 2}""#)).is_err());
+}
+
+#[test]
+fn test_parse_locs() {
+    let expr = parse("a + b * c");
+    assert_eq!(*expr.get_meta(), loc(0, 9));
+    let Expr::Binop(binop) = &*expr.get() else {
+        panic!("expected a binop");
+    };
+    assert_eq!(*binop.lhs.get_meta(), loc(0, 1));
+    assert_eq!(*binop.rhs.get_meta(), loc(4, 9));
+
+    assert_eq!(*parse("(a + b)").get_meta(), loc(0, 7));
+    assert_eq!(*parse("-x").get_meta(), loc(0, 2));
+    assert_eq!(*parse("f(x, y)").get_meta(), loc(0, 7));
+    assert_eq!(*parse("v:len()").get_meta(), loc(0, 7));
+    assert_eq!(*parse("*v:normalize()").get_meta(), loc(0, 14));
+    assert_eq!(*parse("x.y").get_meta(), loc(0, 3));
+}
+
+#[test]
+fn test_parse_errors() {
+    let parse_err = |source| parse_expr(&mut make_lexer(source)).unwrap_err();
+    assert!(matches!(parse_err("1 +"), ParseError::OutOfTokens));
+    assert!(matches!(
+        parse_err("f(a b)"),
+        ParseError::Unexpected {
+            found: Token::Ident,
+            ..
+        }
+    ));
+    assert!(matches!(parse_err("(1"), ParseError::OutOfTokens));
+    assert!(matches!(
+        parse_err("*x + 1"),
+        ParseError::Unexpected {
+            found: Token::Plus,
+            ..
+        }
+    ));
+    assert!(matches!(
+        parse_err("a + )"),
+        ParseError::Unexpected {
+            expected: "expression",
+            found: Token::RParen,
+            loc: Loc {
+                start: 4,
+                end: 5,
+                ..
+            },
+        }
+    ));
+    assert!(matches!(
+        parse_err("a + $"),
+        ParseError::UnrecognizedToken(_)
+    ));
+    for (source, start, end) in [("\"{}\"", 1, 3), ("\"a{ }b\"", 2, 5)] {
+        let ParseError::InvalidLiteral(errors) = parse_err(source) else {
+            panic!("expected an invalid literal in {source}");
+        };
+        assert_eq!(errors.len(), 1, "parsing {source}");
+        assert_eq!(errors[0].kind, LiteralErrorKind::EmptyInterpolation);
+        assert_eq!((errors[0].span.start, errors[0].span.end), (start, end));
+    }
+}
+
+fn parse_mod(source: &str) -> Node<Expr<'static, Loc>, Loc> {
+    parse_module(&mut make_lexer(source)).unwrap_or_else(|e| panic!("parsing {source}: {e:?}"))
+}
+
+/// Parses each module and compares it with the tree built by [helpers], which
+/// must print identically
+fn assert_parses_module(cases: Vec<(&str, Node<Expr<'static, Loc>, Loc>)>) {
+    for (source, expected) in cases {
+        assert_eq!(
+            print(&parse_mod(source)),
+            print(&expected),
+            "parsing {source}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_module_fields() {
+    assert_parses_module(vec![
+        ("", estruct([])),
+        ("\n\n", estruct([])),
+        (
+            "x 1\ny \"two\"",
+            estruct([field(vid("x"), ei32(1)), field(vid("y"), estring("two"))]),
+        ),
+        (
+            "x 1, y 2,",
+            estruct([field(vid("x"), ei32(1)), field(vid("y"), ei32(2))]),
+        ),
+        (
+            "Vector3 (x F32, y F32)",
+            estruct([field(
+                tid("Vector3"),
+                estruct([field(vid("x"), etid("F32")), field(vid("y"), etid("F32"))]),
+            )]),
+        ),
+        (
+            "Vector3 (x F32 y F32)",
+            estruct([field(
+                tid("Vector3"),
+                estruct([field(vid("x"), etid("F32")), field(vid("y"), etid("F32"))]),
+            )]),
+        ),
+        (
+            "Vector3 (\n\tx F32\n\n\tzero Vector3(0, 0)\n)",
+            estruct([field(
+                tid("Vector3"),
+                estruct([
+                    field(vid("x"), etid("F32")),
+                    fspacer(),
+                    field(
+                        vid("zero"),
+                        ecall(etid("Vector3"), args([arg(ei32(0)), arg(ei32(0))])),
+                    ),
+                ]),
+            )]),
+        ),
+        ("unit ()", estruct([field(vid("unit"), estruct([]))])),
+        (
+            "..Base\nv ..Vector3(x 1)",
+            estruct([
+                inline(etid("Base").get().clone()),
+                field(
+                    vid("v"),
+                    econstructor(tid("Vector3"), [field(vid("x"), ei32(1))]),
+                ),
+            ]),
+        ),
+    ]);
+}
+
+#[test]
+fn test_parse_doc_comments() {
+    assert_parses_module(vec![
+        (
+            "--- A point\n--- in space\nPoint (x I32)",
+            estruct([
+                fdoc("A point\nin space"),
+                field(tid("Point"), estruct([field(vid("x"), etid("I32"))])),
+            ]),
+        ),
+        (
+            "Point (\n\t---   across\n\t---\n\tx I32\n\n\t--- up\n\ty I32\n)",
+            estruct([field(
+                tid("Point"),
+                estruct([
+                    fdoc("  across\n"),
+                    field(vid("x"), etid("I32")),
+                    fspacer(),
+                    fdoc("up"),
+                    field(vid("y"), etid("I32")),
+                ]),
+            )]),
+        ),
+    ]);
+    assert_eq!(
+        print(&parse_mod("--- doubles\n--- its argument\nf(x I32) x * 2")),
+        "(\n    --- doubles\n    --- its argument\n    f(x I32) x * 2\n)"
+    );
+    assert!(matches!(
+        parse_module(&mut make_lexer("x 1\n--- dangling")),
+        Err(ParseError::Unexpected {
+            found: Token::DocComment,
+            loc: Loc {
+                start: 4,
+                end: 16,
+                ..
+            },
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse_module(&mut make_lexer("p (x 1\n--- dangling\n)")),
+        Err(ParseError::Unexpected {
+            found: Token::DocComment,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parse_declared_funcs() {
+    assert_parses_module(vec![
+        (
+            "ToString (\n\tto_string()\n)",
+            estruct([field(
+                tid("ToString"),
+                estruct([field(vid("to_string"), efunc(eparams([]), estruct([])))]),
+            )]),
+        ),
+        (
+            "x (to_string())",
+            estruct([field(vid("x"), ecall(evid("to_string"), args([])))]),
+        ),
+    ]);
+    assert!(parse_module(&mut make_lexer("p (x, y)")).is_err());
+}
+
+#[test]
+fn test_parse_docs_example() {
+    let module = parse_mod(include_str!("../../docs/example.lx"));
+    let printed = print(&module);
+    let trimmed: Vec<_> = printed.lines().map(str::trim_end).collect();
+    assert_eq!(
+        trimmed.join("\n"),
+        r#"(
+    Vector3 (
+        x F32
+        y F32
+        z F32
+
+        new(x F32, y F32, z F32) Vector3(x, y, z)
+
+        Vector3:len_sq() x ^ 2 + y ^ 2 + z ^ 2
+        Vector3:len() sqrt(len_sq())
+
+        Vector3:to_string() "({x}, {y}, {z})"
+    )
+
+    Person (
+        name String
+        age U8
+
+        new(name String, age U8) Person(name, age)
+
+        Person:to_string() "{name} (age {age})"
+    )
+
+    DynToString (self (), self:to_string() String)
+
+    main() {
+        my_list []DynToString = []
+
+        my_list ++= Vector3.new(1.0, 2.0, 3.0):DynToString(Vector3.to_string)
+        my_list ++= Vector3.new(4.0, 5.0, 6.0):DynToString(Vector3.to_string)
+        my_list ++= Person.new("Alexander", 20):DynToString(Person.to_string)
+
+        strings = my_list:map((obj) obj:to_string())
+    }
+
+    ToString (to_string() ())
+)"#
+    );
+    // the printed module reparses as a struct expression to the same tree
+    assert_eq!(print(&parse(&printed)), printed);
+}
+
+#[test]
+fn test_parse_module_funcs() {
+    assert_parses_module(vec![
+        (
+            "main() 1",
+            estruct([field(vid("main"), efunc(eparams([]), ei32(1)))]),
+        ),
+        (
+            "add(a I32, *b I32) a + b",
+            estruct([field(
+                vid("add"),
+                efunc(
+                    eparams([
+                        param(vid("a"), etid("I32")),
+                        param_mut(vid("b"), etid("I32")),
+                    ]),
+                    add(evid("a"), evid("b")),
+                ),
+            )]),
+        ),
+        (
+            "V (new(x F32) V(x), x F32)",
+            estruct([field(
+                tid("V"),
+                estruct([
+                    field(
+                        vid("new"),
+                        efunc(
+                            eparams([param(vid("x"), etid("F32"))]),
+                            ecall(etid("V"), args([arg(evid("x"))])),
+                        ),
+                    ),
+                    field(vid("x"), etid("F32")),
+                ]),
+            )]),
+        ),
+    ]);
+}
+
+#[test]
+fn test_parse_blocks() {
+    assert_parses_module(vec![
+        (
+            "main() {\n\tx = 1\n\ty I32 = 2\n\n\ty += x\n\ty := y * 2\n\tprint(y)\n}",
+            estruct([field(
+                vid("main"),
+                efunc(
+                    eparams([]),
+                    eblock([
+                        sbind(vid("x"), ei32(1)),
+                        sbindmut(vid("y"), etid("I32"), ei32(2)),
+                        sspacer(),
+                        sadd(evid("y"), evid("x")),
+                        swrite(evid("y"), mul(evid("y"), ei32(2))),
+                        sexpr(ecall(evid("print"), args([arg(evid("y"))]))),
+                    ]),
+                ),
+            )]),
+        ),
+        (
+            "f() { x = 1, x ++= \"a\", v.x -= 1 }",
+            estruct([field(
+                vid("f"),
+                efunc(
+                    eparams([]),
+                    eblock([
+                        sbind(vid("x"), ei32(1)),
+                        sconcat(evid("x"), estring("a")),
+                        ssub(eproj(evid("v"), vid("x")), ei32(1)),
+                    ]),
+                ),
+            )]),
+        ),
+        (
+            "f() {}",
+            estruct([field(vid("f"), efunc(eparams([]), eblock([])))]),
+        ),
+    ]);
+}
+
+#[test]
+fn test_parse_struct_or_group() {
+    assert_parses(vec![
+        ("(x)", evid("x")),
+        (
+            "(f(x) + 1)",
+            add(ecall(evid("f"), args([arg(evid("x"))])), ei32(1)),
+        ),
+        ("(\n\ta + b\n)", add(evid("a"), evid("b"))),
+        ("(x 1)", estruct([field(vid("x"), ei32(1))])),
+        ("(\n\tx 1\n)", estruct([field(vid("x"), ei32(1))])),
+    ]);
+}
+
+#[test]
+fn test_parse_module_errors() {
+    let parse_err = |source| parse_module(&mut make_lexer(source)).unwrap_err();
+    assert!(matches!(parse_err("x (y 1"), ParseError::OutOfTokens));
+    assert!(matches!(
+        parse_err("1"),
+        ParseError::ExpectFailed {
+            expected: Token::Ident,
+            found: Token::Number,
+            ..
+        }
+    ));
+    assert!(matches!(
+        parse_err("f() { x = 1 y = 2 }"),
+        ParseError::Unexpected {
+            expected: "`,` or `}`",
+            ..
+        }
+    ));
+    assert!(matches!(
+        parse_err("f() { x I32 }"),
+        ParseError::ExpectFailed {
+            expected: Token::Equals,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_parse_module_locs() {
+    let module = parse_mod("a 1\nf(x I32) { x }");
+    assert_eq!(*module.get_meta(), loc(0, 18));
+    let Expr::Struct(struct_) = &*module.get() else {
+        panic!("expected a struct");
+    };
+    assert_eq!(*struct_.fields[0].get_meta(), loc(0, 3));
+    assert_eq!(*struct_.fields[1].get_meta(), loc(4, 18));
+}
+
+#[test]
+fn test_parse_methods() {
+    assert_parses_module(vec![
+        (
+            "Vector3:len_sq() x^2 + y^2",
+            estruct([method(
+                tid("Vector3"),
+                vid("len_sq"),
+                eparams([]),
+                add(pow(evid("x"), ei32(2)), pow(evid("y"), ei32(2))),
+            )]),
+        ),
+        (
+            "*Vector3:scale(by F32) { x *= by }",
+            estruct([method_mut(
+                tid("Vector3"),
+                vid("scale"),
+                eparams([param(vid("by"), etid("F32"))]),
+                eblock([smul(evid("x"), evid("by"))]),
+            )]),
+        ),
+        (
+            "self:to_string() String",
+            estruct([method(
+                vid("self"),
+                vid("to_string"),
+                eparams([]),
+                etid("String"),
+            )]),
+        ),
+    ]);
+    assert_eq!(print(&parse_mod("*V:f() 1")), "(*V:f() 1)",);
+    assert!(matches!(
+        parse_module(&mut make_lexer("*x 1")),
+        Err(ParseError::ExpectFailed {
+            expected: Token::Colon,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parse_named_args() {
+    assert_parses(vec![
+        (
+            "print(\"hi\", newline=true)",
+            ecall(
+                evid("print"),
+                args([arg(estring("hi")), arg_named(vid("newline"), evid("true"))]),
+            ),
+        ),
+        (
+            "f(a=*x, b=1 + 2)",
+            ecall(
+                evid("f"),
+                args([
+                    Arg {
+                        name: Some(vid("a")),
+                        expr: evid("x"),
+                        is_mut: true,
+                    }
+                    .elt(),
+                    arg_named(vid("b"), add(ei32(1), ei32(2))),
+                ]),
+            ),
+        ),
+    ]);
+    assert_eq!(*parse("f(a=1)").get_meta(), loc(0, 6));
+    let call = parse("f(x, name=1)");
+    let Expr::Call(call) = &*call.get() else {
+        panic!("expected a call");
+    };
+    assert_eq!(*call.args.get().args[1].get_meta(), loc(5, 11));
+}
+
+#[test]
+fn test_parse_default_params() {
+    assert_parses_module(vec![(
+        "print(message String, newline Bool = false) 1",
+        estruct([field(
+            vid("print"),
+            efunc(
+                eparams([
+                    param(vid("message"), etid("String")),
+                    param_default(vid("newline"), etid("Bool"), evid("false")),
+                ]),
+                ei32(1),
+            ),
+        )]),
+    )]);
+    assert_eq!(
+        print(&parse_mod("f(a I32 = 1, b I32) a")),
+        "(f(a I32 = 1, b I32) a)"
+    );
+}
+
+#[test]
+fn test_parse_enums() {
+    assert_parses_module(vec![
+        (
+            "Bool <true (), false ()>\n..Bool",
+            estruct([
+                field(
+                    tid("Bool"),
+                    eenum([
+                        field(vid("true"), estruct([])),
+                        field(vid("false"), estruct([])),
+                    ]),
+                ),
+                inline(etid("Bool").get().clone()),
+            ]),
+        ),
+        (
+            "Expr <\n\tIdent\n\tPrim <I32, String>\n\tStruct (fields I32)\n>",
+            estruct([field(
+                tid("Expr"),
+                eenum([
+                    field(tid("Ident"), eident(unshadow(tid("Ident"), 1))),
+                    field(
+                        tid("Prim"),
+                        eenum([
+                            field(tid("I32"), eident(unshadow(tid("I32"), 1))),
+                            field(tid("String"), eident(unshadow(tid("String"), 1))),
+                        ]),
+                    ),
+                    field(tid("Struct"), estruct([field(vid("fields"), etid("I32"))])),
+                ]),
+            )]),
+        ),
+        (
+            "x Expr.Struct(fields=1)",
+            estruct([field(
+                vid("x"),
+                ecall(
+                    eproj(etid("Expr"), tid("Struct")),
+                    args([arg_named(vid("fields"), ei32(1))]),
+                ),
+            )]),
+        ),
+    ]);
+    assert_eq!(
+        print(&parse_mod("Bool <true (), false ()>")),
+        "(Bool <true (), false ()>)"
+    );
+}
+
+#[test]
+fn test_parse_generics() {
+    assert_parses_module(vec![
+        (
+            "Dyn<T> (self ())",
+            estruct([field(
+                tid("Dyn"),
+                egeneric(
+                    [gparam(tid("T"))],
+                    estruct([field(vid("self"), estruct([]))]),
+                ),
+            )]),
+        ),
+        (
+            "any_add<T>(obj DynAdd<T>) obj",
+            estruct([field(
+                vid("any_add"),
+                egeneric(
+                    [gparam(tid("T"))],
+                    efunc(
+                        eparams([param(vid("obj"), einstantiate(etid("DynAdd"), [etid("T")]))]),
+                        evid("obj"),
+                    ),
+                ),
+            )]),
+        ),
+        (
+            "ToString<Self Vector3> (x 1)",
+            estruct([field(
+                tid("ToString"),
+                egeneric(
+                    [gparam_value(tid("Self"), etid("Vector3"))],
+                    estruct([field(vid("x"), ei32(1))]),
+                ),
+            )]),
+        ),
+        (
+            "adder DynAdd<I32, String>(x, y)",
+            estruct([field(
+                vid("adder"),
+                ecall(
+                    einstantiate(etid("DynAdd"), [etid("I32"), etid("String")]),
+                    args([arg(evid("x")), arg(evid("y"))]),
+                ),
+            )]),
+        ),
+        (
+            "x v:as_dyn<ToString>()",
+            estruct([field(
+                vid("x"),
+                emethod(
+                    einstantiate(evid("as_dyn"), [etid("ToString")]),
+                    args([arg(evid("v"))]),
+                ),
+            )]),
+        ),
+        (
+            "Prim <I32, String>",
+            estruct([field(
+                tid("Prim"),
+                eenum([
+                    field(tid("I32"), eident(unshadow(tid("I32"), 1))),
+                    field(tid("String"), eident(unshadow(tid("String"), 1))),
+                ]),
+            )]),
+        ),
+    ]);
+    assert_eq!(
+        print(&parse_mod("Dyn<T> (\n\tself ()\n\t..T<self>\n)")),
+        "(Dyn<T> (self (), ..T<self>))"
+    );
+    assert_eq!(print(&parse_mod("f<T>(x T) x")), "(f<T>(x T) x)");
+}
+
+#[test]
+fn test_parse_nested_generics() {
+    assert_parses_module(vec![
+        (
+            "v D<L<I32>>",
+            estruct([field(
+                vid("v"),
+                einstantiate(etid("D"), [einstantiate(etid("L"), [etid("I32")])]),
+            )]),
+        ),
+        (
+            "W<Self L<I32>> (x 1)",
+            estruct([field(
+                tid("W"),
+                egeneric(
+                    [gparam_value(
+                        tid("Self"),
+                        einstantiate(etid("L"), [etid("I32")]),
+                    )],
+                    estruct([field(vid("x"), ei32(1))]),
+                ),
+            )]),
+        ),
+    ]);
+    assert_eq!(
+        print(&parse_mod("D<T> (x T)\nL<T> (x T)\nv D<L<I32>>")),
+        "(D<T> (x T), L<T> (x T), v D<L<I32>>)"
+    );
+    // each level is parsed once, so deep nesting parses quickly
+    let depth = 40;
+    let source = format!("v {}I32{}", "D<".repeat(depth), ">".repeat(depth));
+    assert_eq!(print(&parse_mod(&source)), format!("({source})"));
+}
+
+#[test]
+fn test_parse_conditionals() {
+    assert_eq!(
+        print(&parse_mod(
+            "f(len F32) {\n\t?(len > 0.0) {\n\t\t=> len\n\t}\n\tlen\n}"
+        )),
+        "(f(len F32) {\n        ?(len > 0.0) { => len }\n        len\n    })"
+    );
+    assert_eq!(
+        print(&parse_mod("x a + 1 <= b == c != d")),
+        "(x a + 1 <= b == c != d)"
+    );
+    assert_eq!(print(&parse_mod("x a < b\ny a<b")), "(x a < b, y a < b)");
+    assert_eq!(
+        print(&parse_mod("E <A (), B ()>\nx ?(a >= b) 1")),
+        "(E <A (), B ()>, x ?(a >= b) 1)"
+    );
+}
+
+#[test]
+fn test_parse_lists() {
+    assert_eq!(
+        print(&parse_mod(
+            "f() {\n\tmy_list []I32 = []\n\tmy_list ++= 1\n}"
+        )),
+        "(f() {\n        my_list []I32 = []\n        my_list ++= 1\n    })"
+    );
+    assert_eq!(print(&parse_mod("x [\n\t1\n\t2\n]")), "(x [1, 2])");
+    assert_eq!(
+        print(&parse_mod("People (members []Person, first [a, b + 1])")),
+        "(People (members []Person, first [a, b + 1]))"
+    );
+    assert_eq!(print(&parse_mod("x [[1], []]")), "(x [[1], []])");
+}
+
+#[test]
+fn test_parse_lambdas() {
+    assert_eq!(
+        print(&parse_mod("add (a, b) a + b\ninc (x I32) x + 1")),
+        "(add(a, b) a + b, inc(x I32) x + 1)"
+    );
+    assert_eq!(
+        print(&parse_mod("f(xs []I32) xs:map((x) x * 2)")),
+        "(f(xs []I32) xs:map((x) x * 2))"
+    );
+    assert_eq!(
+        print(&parse_mod("s (g (a = 1) a, y ((x) x)(2))")),
+        "(s (g(a = 1) a, y ((x) x)(2)))"
+    );
+    assert_eq!(print(&parse_mod("x (a) (b)")), "(x(a) b)");
+    assert_eq!(
+        print(&parse_mod("x (a)\ny (a 1, b 2)")),
+        "(x a, y (a 1, b 2))"
+    );
+}
+
+#[test]
+fn test_parse_method_without_parens() {
+    let module = parse_mod("f(v V) {\n\t*v:normalize\n\tv:len:sqrt + v:dot(v)\n}");
+    assert_eq!(
+        print(&module),
+        "(f(v V) {\n        *v:normalize()\n        v:len():sqrt() + v:dot(v)\n    })"
+    );
+    assert_eq!(
+        module.get().pretty_print(
+            &mut PrettyPrintContext::default()
+                .with_colors(false)
+                .with_omit_empty_parens(true)
+        ),
+        "(f(v V) {\n        *v:normalize\n        v:len:sqrt + v:dot(v)\n    })"
+    );
+    assert_eq!(
+        print(&parse_mod("s (x xs:map((o) o:to_string), y a:b<T>)")),
+        "(s (x xs:map((o) o:to_string()), y a:b<T>()))"
+    );
+}
+
+#[test]
+fn test_parse_operator_idents() {
+    assert_eq!(
+        print(&parse_mod(
+            "(+)(lhs V, rhs V) V(lhs.x + rhs.x)\nx (+)(1, 2)\ny ^(*)"
+        )),
+        "((+)(lhs V, rhs V) V(lhs.x + rhs.x), x (+)(1, 2), y ^(*))"
+    );
+    assert_eq!(
+        print(&parse_mod(
+            "V (\n\tx F32\n\tV:(==)(rhs V) x == rhs.x\n)\ns ((<) (a, b) a < b)"
+        )),
+        "(V (x F32, V:(==)(rhs V) x == rhs.x), s ((<)(a, b) a < b))"
+    );
+    assert_eq!(print(&parse_mod("x a:(++)(b)")), "(x a:(++)(b))");
+}
+
+#[test]
+fn test_parse_literal_params() {
+    assert_eq!(
+        print(&parse_mod(
+            "fibonacci(0) 0\nfibonacci(1) 1\nfibonacci(x U32) fibonacci(x - 1) + fibonacci(x - 2)"
+        )),
+        "(\n    fibonacci(0) 0\n    fibonacci(1) 1\n    fibonacci(x U32) fibonacci(x - 1) + fibonacci(x - 2)\n)"
+    );
+    assert_eq!(
+        print(&parse_mod("greet(\"hi\", n I32) n")),
+        "(greet(\"hi\", n I32) n)"
+    );
+}
+
+#[test]
+fn test_parse_overrides() {
+    assert_eq!(
+        print(&parse_mod("add(x I32, y I32) x + y\nadd!(2, 2) 5")),
+        "(add(x I32, y I32) x + y, add!(2, 2) 5)"
+    );
+    assert_eq!(
+        print(&parse_mod("V (\n\tx I32\n\tV:len!() 0\n)")),
+        "(V (x I32, V:len!() 0))"
+    );
+    assert_eq!(
+        print(&parse_mod("add {\n\t(2, 2) 5\n\t(x I32, y I32) x + y\n}")),
+        "(\n    add {\n        (2, 2) 5\n        (x I32, y I32) x + y\n    }\n)"
+    );
+    assert_eq!(
+        print(&parse_mod("add { (2, 2) 5, (x I32, y I32) x + y }")),
+        "(\n    add {\n        (2, 2) 5\n        (x I32, y I32) x + y\n    }\n)"
+    );
+    assert_eq!(print(&parse_mod("x { (1 + 2) }")), "(x { 1 + 2 })");
+    assert!(parse_module(&mut Lexer::new("add! (2, 2) 5")).is_err());
+}
+
+#[test]
+fn test_parse_partial_inline() {
+    assert_eq!(
+        print(&parse_mod("..std.(to_string)")),
+        "(..std.(to_string))"
+    );
+    assert_eq!(
+        print(&parse_mod("..std.io.(show to_string, print)")),
+        "(..std.io.(show to_string, print))"
+    );
+    assert_eq!(
+        print(&parse_mod("..ops.(\n\t(+)\n\teq (==)\n)")),
+        "(..ops.((+), eq (==)))"
+    );
+    assert_eq!(print(&parse_mod("x a.b.c")), "(x a.b.c)");
+    assert!(parse_module(&mut Lexer::new("..std.(a b c)")).is_err());
+}
@@ -1,4 +1,6 @@
+use crate::ast::{Prim, widens};
 use crate::ir::*;
+use crate::node::*;
 
 pub trait TypeRelation {
     fn is_subtype_of(&self, other: &Self) -> bool;
@@ -10,29 +12,69 @@ pub trait TypeRelation {
     }
 }
 
-impl TypeRelation for Expr {
+impl<'a, M: NodeMeta> TypeRelation for Expr<'a, M> {
     fn is_subtype_of(&self, other: &Self) -> bool {
         match (self, other) {
-            (Expr::Struct(lhs), Expr::Struct(rhs)) => lhs.borrow().is_subtype_of(&rhs.borrow()),
+            // `_` is the type of every value
+            (_, Expr::Ident(rhs)) if rhs.is_void() => true,
+            // a primitive type is a subtype of those it converts to losslessly
+            (Expr::Ident(lhs), Expr::Ident(rhs)) => {
+                lhs == rhs || (lhs.nshadow == 0 && rhs.nshadow == 0 && widens(&lhs.name, &rhs.name))
+            }
+            // a literal is a singleton type, which is a subtype of each
+            // primitive type it is a value of
+            (Expr::Prim(lhs), Expr::Ident(rhs)) => rhs.nshadow == 0 && lhs.fits(&rhs.name),
             (Expr::Prim(lhs), Expr::Prim(rhs)) => lhs.is_subtype_of(rhs),
+            // a struct constructed from a type is an instance of that type
+            (Expr::Call(call), Expr::Ident(rhs)) => constructs(&call.func.get(), rhs),
+            (Expr::Constructor(constructor), Expr::Ident(rhs)) => constructor.ty == *rhs,
+            (Expr::List(list), Expr::ListType(rhs)) => {
+                list.items.iter().all(|item| item.is_subtype_of(&rhs.item))
+            }
+            (Expr::Struct(lhs), Expr::Struct(rhs)) => lhs.is_subtype_of(rhs),
             (Expr::Func(lhs), Expr::Func(rhs)) => lhs.is_subtype_of(rhs),
+            (Expr::ListType(lhs), Expr::ListType(rhs)) => lhs.item.is_subtype_of(&rhs.item),
             _ => false,
         }
     }
 }
 
-impl TypeRelation for Prim {
+/// Returns `true` if calling `func` constructs a value of the type named by
+/// `ty`, as calling `Vector3` or `Dyn<I32>` does
+fn constructs<M: NodeMeta>(func: &Expr<'_, M>, ty: &Ident) -> bool {
+    match func {
+        Expr::Ident(ident) => ident.is_type && ident == ty,
+        Expr::Instantiate(instantiate) => constructs(&instantiate.expr.get(), ty),
+        _ => false,
+    }
+}
+
+impl<'a, M: NodeMeta> TypeRelation for Node<Expr<'a, M>, M> {
+    fn is_subtype_of(&self, other: &Self) -> bool {
+        self.get().is_subtype_of(&other.get())
+    }
+}
+
+/// A missing type accepts any value, like `_`
+impl<T: TypeRelation> TypeRelation for Option<T> {
     fn is_subtype_of(&self, other: &Self) -> bool {
         match (self, other) {
-            (Prim::I32(lhs), Prim::I32(rhs)) => lhs == rhs,
-            (Prim::String(lhs), Prim::String(rhs)) => lhs == rhs,
-            (Prim::Char(lhs), Prim::Char(rhs)) => lhs == rhs,
-            _ => false,
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(lhs), Some(rhs)) => lhs.is_subtype_of(rhs),
         }
     }
 }
 
-impl TypeRelation for Struct {
+/// Literals of different types are the same singleton type when they convert
+/// to the same value, as `0u32` is `0`
+impl TypeRelation for Prim {
+    fn is_subtype_of(&self, other: &Self) -> bool {
+        other.cast(self.type_name()).as_ref() == Some(self)
+    }
+}
+
+impl<'a, M: NodeMeta> TypeRelation for Struct<'a, M> {
     fn is_subtype_of(&self, other: &Self) -> bool {
         // must have same or more number of fields
         if self.fields.len() < other.fields.len() {
@@ -40,10 +82,11 @@ impl TypeRelation for Struct {
         }
 
         // each field of `other` must exist in `self`, and it must be a subtype
-        for (ident, other_expr) in &other.fields {
-            match self.fields.get(ident) {
+        for field in &other.fields {
+            let field = field.get();
+            match self.get(&field.ident.name) {
                 Some(self_expr) => {
-                    if !self_expr.is_subtype_of(other_expr) {
+                    if !self_expr.is_subtype_of(&field.value) {
                         return false;
                     }
                 }
@@ -55,29 +98,27 @@ impl TypeRelation for Struct {
     }
 }
 
-impl TypeRelation for Func {
+impl<'a, M: NodeMeta> TypeRelation for Func<'a, M> {
     fn is_subtype_of(&self, other: &Self) -> bool {
         // arguments must match
-        if self.args.borrow().args.len() != other.args.borrow().args.len() {
+        let self_params = &self.params.get().params;
+        let other_params = &other.params.get().params;
+        if self_params.len() != other_params.len() {
             return false;
         }
 
-        for ((_, (self_mut, self_arg)), (_, (other_mut, other_arg))) in self
-            .args
-            .borrow()
-            .args
-            .iter()
-            .zip(other.args.borrow().args.iter())
-        {
+        for (self_param, other_param) in self_params.iter().zip(other_params) {
+            let (self_param, other_param) = (self_param.get(), other_param.get());
+
             // `(I32)String` is a subtype of `(*I32)String`, but
             // `(*I32)String` is _not_ a subtype of `(I32)String`
-            if *self_mut && !other_mut {
+            if self_param.is_mut && !other_param.is_mut {
                 return false;
             }
 
             // function arguments are contravariant
             // so `(T)() <: (U)()` requires that `T :> U`
-            if !self_arg.is_supertype_of(other_arg) {
+            if !self_param.ty.is_supertype_of(&other_param.ty) {
                 return false;
             }
         }